@@ -1,6 +1,8 @@
+use anyhow::{bail, Result};
 use clap::{Parser, Subcommand};
 
-use crate::tts::Voices;
+use crate::input::Input;
+use crate::subreddit::{CommentStrategy, SearchSort, Sort, Time};
 
 /// Create reddit text to speech videos without any editing.
 #[derive(Debug, Parser)]
@@ -8,6 +10,38 @@ use crate::tts::Voices;
 pub struct Args {
     #[clap(subcommand)]
     pub command: Commands,
+
+    /// Path of redtake.toml configuration file.
+    /// By default redtake.toml is read from current directory if it exists.
+    #[clap(long, global = true)]
+    pub config: Option<String>,
+
+    /// Directory used for caching reddit responses, screenshots and tts audio.
+    /// Defaults to $XDG_CACHE_HOME/redtake or ~/.cache/redtake.
+    #[clap(long, global = true)]
+    pub cache_dir: Option<String>,
+
+    /// Minutes after which cached reddit responses and screenshots are fetched again.
+    #[clap(long, global = true, default_value_t = 60)]
+    pub cache_ttl: u64,
+
+    /// Disable caching.
+    #[clap(long, global = true)]
+    pub no_cache: bool,
+
+    /// Use only cached reddit responses, screenshots and tts audio.
+    /// Fails on a cache miss instead of making network requests.
+    #[clap(long, global = true, conflicts_with = "no-cache")]
+    pub offline: bool,
+
+    /// Path of history file which records threads and comments used in projects.
+    /// Defaults to $XDG_DATA_HOME/redtake/history.jsonl or ~/.local/share/redtake/history.jsonl.
+    #[clap(long, global = true)]
+    pub history_file: Option<String>,
+
+    /// User agent used for all http requests.
+    #[clap(long, global = true, default_value = crate::client::USER_AGENT)]
+    pub user_agent: String,
 }
 
 #[derive(Debug, Subcommand)]
@@ -15,6 +49,7 @@ pub enum Commands {
     New(New),
     Render(Render),
     Tts(Tts),
+    History(History),
     /// List available voices for tts.
     /// Listed voices are in format of [language_code - country_code - service_provider - voice_name].
     Voices,
@@ -24,16 +59,33 @@ pub enum Commands {
 #[derive(Debug, clap::Args)]
 pub struct New {
     /// Links from reddit.com website.
-    /// Supported patterns are r/AskReddit and https://reddit.com/r/...
-    /// If input doesn't specifies any subreddit thread then redtake will fetch hot threads from that subreddit.
-    #[clap(required = true)]
-    pub input: String,
+    /// Supported patterns are r/AskReddit, https://reddit.com/r/..., https://old.reddit.com/r/...,
+    /// https://redd.it/..., share links (r/.../s/...), bare thread ids, u/... and user/.../m/...
+    /// Multiple subreddits can be combined like r/AskReddit+NoStupidQuestions.
+    /// If input doesn't specifies any subreddit thread then redtake will fetch threads from that subreddit using --sort.
+    /// Input can be omitted when using --search or --from-file.
+    #[clap(required_unless_present_any = ["search", "from-file"])]
+    pub input: Option<Input>,
+
+    /// Create projects from local json dumps instead of fetching them.
+    /// It can be a reddit thread page or listing json, thread.json saved by redtake or a directory of them.
+    #[clap(long, conflicts_with_all = &["input", "search"])]
+    pub from_file: Option<String>,
+
+    /// Search reddit for threads matching query instead.
+    /// Search is restricted to subreddit if input is a subreddit.
+    #[clap(long)]
+    pub search: Option<String>,
+
+    /// Sort order used for search results.
+    #[clap(long, arg_enum, default_value_t = SearchSort::Relevance)]
+    pub search_sort: SearchSort,
 
     /// Use light theme for taking screenshots instead of dark theme.
     #[clap(long)]
     pub light: bool,
 
-    /// Maximum limit to fetch reddit hot threads from subreddit.
+    /// Maximum limit to fetch reddit threads from subreddit.
     #[clap(long, default_value_t = 25)]
     pub limit: u16,
 
@@ -45,6 +97,36 @@ pub struct New {
     #[clap(long, default_value_t = 500)]
     pub max_length: usize,
 
+    /// Maximum depth of replies to narrate below each comment.
+    /// Replies are narrated just after their parent comment.
+    /// Defaults to 0 for threads and 10 for comment permalinks.
+    #[clap(long)]
+    pub depth: Option<u8>,
+
+    /// Expand "load more comments" stubs using reddit morechildren api.
+    #[clap(long)]
+    pub expand_more: bool,
+
+    /// Strategy used for selecting comments to narrate.
+    #[clap(long, arg_enum, default_value_t = CommentStrategy::First)]
+    pub comment_strategy: CommentStrategy,
+
+    /// Minimum score of comments (and replies) to narrate.
+    #[clap(long, allow_hyphen_values = true)]
+    pub min_comment_score: Option<i64>,
+
+    /// Minimum score of threads to generate projects from.
+    #[clap(long, allow_hyphen_values = true)]
+    pub min_score: Option<i64>,
+
+    /// Minimum number of comments of threads to generate projects from.
+    #[clap(long)]
+    pub min_comments: Option<u64>,
+
+    /// Maximum age (in hours) of threads to generate projects from.
+    #[clap(long)]
+    pub max_age: Option<f64>,
+
     /// Allow generating projects from 18+ threads.
     #[clap(long)]
     pub nsfw: bool,
@@ -53,14 +135,28 @@ pub struct New {
     #[clap(short, long)]
     pub tts_proxy: bool,
 
-    /// Skip creating existing projects.
+    /// Sort order used for fetching threads from subreddit.
+    #[clap(long, arg_enum, default_value_t = Sort::Hot)]
+    pub sort: Sort,
+
+    /// Time window used with top and controversial sort orders and search.
+    /// When not given, reddit default is used which is day for subreddits and all for search.
+    #[clap(long, arg_enum)]
+    pub time: Option<Time>,
+
+    /// Skip creating existing projects and threads present in history.
     /// By default projects are overriden if they already exists.
     #[clap(long)]
     pub skip: bool,
 
+    /// Narrate comments which were already used in other projects of same thread.
+    #[clap(long)]
+    pub reuse_comments: bool,
+
     /// Select voice model for speech synthesis.
-    #[clap(long, arg_enum, default_value_t = Voices::EnUSTiktokFemale2, hide_possible_values = true)]
-    pub voice: Voices,
+    /// Use `redtake voices` to list available voices.
+    #[clap(long, default_value = "en-us-tiktok-female2")]
+    pub voice: String,
 }
 
 /// Render video using ffmpeg.
@@ -89,14 +185,54 @@ pub struct Tts {
     pub duration: bool,
 
     /// Select voice model for speech synthesis.
-    #[clap(long, arg_enum, default_value_t = Voices::EnUSTiktokFemale2, hide_possible_values = true)]
-    pub voice: Voices,
+    /// Use `redtake voices` to list available voices.
+    #[clap(long, default_value = "en-us-tiktok-female2")]
+    pub voice: String,
 
     /// Use https://warp-co.rs CORS proxy to bypass geolocation restrictions for some tts voices.
     #[clap(short, long)]
     pub tts_proxy: bool,
 }
 
+/// List or prune history of threads used in projects.
+#[derive(Debug, clap::Args)]
+pub struct History {
+    /// Remove entries of thread ids.
+    #[clap(long)]
+    pub remove: Vec<String>,
+
+    /// Remove entries older than these many days.
+    #[clap(long)]
+    pub older_than: Option<f64>,
+
+    /// Remove all entries.
+    #[clap(long, conflicts_with_all = &["remove", "older-than"])]
+    pub clear: bool,
+}
+
+impl New {
+    /// Input after combining it with --search query.
+    pub fn input(&self) -> Result<Input> {
+        if let Some(path) = &self.from_file {
+            return Ok(Input::File(path.into()));
+        }
+
+        match (&self.search, &self.input) {
+            (Some(query), None) => Ok(Input::Search {
+                subreddit: None,
+                query: query.to_owned(),
+            }),
+            (Some(query), Some(Input::Subreddit(subreddit))) => Ok(Input::Search {
+                subreddit: Some(subreddit.to_owned()),
+                query: query.to_owned(),
+            }),
+            (Some(_), Some(_)) => bail!("Search can only be restricted to a subreddit."),
+            (None, Some(input)) => Ok(input.to_owned()),
+            (None, None) => bail!("No input is provided."),
+        }
+    }
+}
+
 pub fn parse() -> Args {
     Args::parse()
 }
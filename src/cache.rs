@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+/// On-disk cache of http responses and synthesized speech.
+/// Entries are stored as `<dir>/<namespace>/<hash of key>`.
+pub struct Cache {
+    dir: PathBuf,
+    offline: bool,
+}
+
+impl Cache {
+    pub fn new(dir: &str, offline: bool) -> Self {
+        Self {
+            dir: PathBuf::from(dir),
+            offline,
+        }
+    }
+
+    /// $XDG_CACHE_HOME/redtake or ~/.cache/redtake
+    pub fn default_dir() -> String {
+        redtake_dir("XDG_CACHE_HOME", ".cache")
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Only cached entries can be used and network requests are not allowed.
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Cached entry of `key` if it is not older than `max_age`.
+    /// Age of entries is ignored in offline mode.
+    pub fn get(&self, namespace: &str, key: &str, max_age: Option<Duration>) -> Option<Vec<u8>> {
+        let path = self.path(namespace, key);
+
+        if !self.offline {
+            if let Some(max_age) = max_age {
+                let age = std::fs::metadata(&path)
+                    .and_then(|x| x.modified())
+                    .ok()?
+                    .elapsed()
+                    .unwrap_or_default();
+
+                if age > max_age {
+                    return None;
+                }
+            }
+        }
+
+        std::fs::read(path).ok()
+    }
+
+    pub fn put(&self, namespace: &str, key: &str, data: &[u8]) -> Result<()> {
+        let path = self.path(namespace, key);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(&path, data)
+            .with_context(|| format!("Couldn't write cache entry {}", path.display()))
+    }
+
+    fn path(&self, namespace: &str, key: &str) -> PathBuf {
+        self.dir.join(namespace).join(format!("{:016x}", fnv1a(key)))
+    }
+}
+
+/// Hash which stays same across runs and rust versions, unlike std hashers.
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// redtake directory inside directory of XDG `var` (like XDG_CACHE_HOME)
+/// or else inside `home_dir` (like .cache) of home directory.
+pub(crate) fn redtake_dir(var: &str, home_dir: &str) -> PathBuf {
+    std::env::var(var)
+        .ok()
+        .filter(|x| !x.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var("HOME")
+                .or_else(|_| std::env::var("USERPROFILE"))
+                .ok()
+                .map(|x| PathBuf::from(x).join(home_dir))
+        })
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("redtake")
+}
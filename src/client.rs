@@ -0,0 +1,421 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::{IntoUrl, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::cache::Cache;
+
+pub const USER_AGENT: &str = concat!(
+    "redtake/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/clitic/redtake)"
+);
+
+/// Credentials of a reddit "script" app.
+#[derive(Debug, Clone)]
+pub struct OAuth {
+    pub client_id: String,
+    pub client_secret: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token_url: String,
+}
+
+struct Token {
+    access_token: String,
+    expires_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    expires_in: Option<u64>,
+    error: Option<String>,
+}
+
+/// Http client shared by reddit and tts requests.
+/// It waits out reddit rate limits and retries transient failures with exponential backoff.
+pub struct Client {
+    cache: Option<Cache>,
+    client: reqwest::blocking::Client,
+    oauth: Option<OAuth>,
+    reddit_ttl: Duration,
+    retries: u32,
+    token: Mutex<Option<Token>>,
+    /// Time until which requests are held back because rate limit is exhausted.
+    wait_until: Mutex<Option<Instant>>,
+}
+
+impl Client {
+    pub fn new(user_agent: &str) -> Result<Self> {
+        Ok(Self {
+            cache: None,
+            client: reqwest::blocking::Client::builder()
+                .user_agent(user_agent)
+                .timeout(Duration::from_secs(60))
+                .build()?,
+            oauth: None,
+            reddit_ttl: Duration::ZERO,
+            retries: 4,
+            token: Mutex::new(None),
+            wait_until: Mutex::new(None),
+        })
+    }
+
+    /// Use reddit api through oauth.reddit.com with bearer tokens.
+    pub fn with_oauth(mut self, oauth: OAuth) -> Self {
+        self.oauth = Some(oauth);
+        self
+    }
+
+    /// Cache reddit responses (upto `reddit_ttl`) and synthesized speech on disk.
+    pub fn with_cache(mut self, cache: Cache, reddit_ttl: Duration) -> Self {
+        self.cache = Some(cache);
+        self.reddit_ttl = reddit_ttl;
+        self
+    }
+
+    pub fn offline(&self) -> bool {
+        self.cache.as_ref().is_some_and(|x| x.offline())
+    }
+
+    /// Get request for reddit api `path` (like /r/AskReddit/hot.json).
+    pub fn reddit(&self, path: &str) -> Result<RequestBuilder> {
+        match &self.oauth {
+            // Tokens are not needed when responses are served from cache.
+            Some(oauth) if !self.offline() => Ok(self
+                .client
+                .get(format!("https://oauth.reddit.com{}", path))
+                .bearer_auth(self.token(oauth)?)),
+            _ => Ok(self.client.get(format!("https://reddit.com{}", path))),
+        }
+    }
+
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    pub fn request<U: IntoUrl>(&self, method: Method, url: U) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    /// Entry of `key` from cache or else fetch and store it in cache.
+    pub fn cached<F>(
+        &self,
+        namespace: &str,
+        key: &str,
+        max_age: Option<Duration>,
+        fetch: F,
+    ) -> Result<Vec<u8>>
+    where
+        F: FnOnce() -> Result<Vec<u8>>,
+    {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return fetch(),
+        };
+
+        if let Some(data) = cache.get(namespace, key, max_age) {
+            return Ok(data);
+        }
+
+        if cache.offline() {
+            bail!(
+                "Cache miss in offline mode for {} entry {:?}",
+                namespace,
+                key.chars().take(100).collect::<String>()
+            );
+        }
+
+        let data = fetch()?;
+        cache.put(namespace, key, &data)?;
+        Ok(data)
+    }
+
+    pub fn send(&self, request: RequestBuilder) -> Result<Response> {
+        if self.offline() {
+            bail!("Network requests are not allowed in offline mode.");
+        }
+
+        let mut attempt = 0;
+
+        loop {
+            self.wait();
+
+            // Requests with streaming bodies can't be cloned and hence aren't retried.
+            let current = match request.try_clone() {
+                Some(current) => current,
+                None => {
+                    let res = request.send()?;
+                    self.update_rate_limit(&res);
+                    return Ok(res.error_for_status()?);
+                }
+            };
+            let can_retry = attempt < self.retries;
+
+            let delay = match current.send() {
+                Ok(res) => {
+                    self.update_rate_limit(&res);
+
+                    if !can_retry
+                        || !(res.status() == StatusCode::TOO_MANY_REQUESTS
+                            || res.status().is_server_error())
+                    {
+                        return Ok(res.error_for_status()?);
+                    }
+
+                    retry_after(&res)
+                }
+                Err(e) if can_retry && (e.is_timeout() || e.is_connect()) => None,
+                Err(e) => return Err(e.into()),
+            };
+
+            std::thread::sleep(delay.unwrap_or_else(|| Duration::from_secs(2_u64.pow(attempt))));
+            attempt += 1;
+        }
+    }
+
+    /// Responses of get requests are cached by their path and query,
+    /// so that same entry is used with or without oauth.
+    pub fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let key = request
+            .try_clone()
+            .and_then(|x| x.build().ok())
+            .filter(|x| x.method() == reqwest::Method::GET)
+            .map(|x| match x.url().query() {
+                Some(query) => format!("{}?{}", x.url().path(), query),
+                None => x.url().path().to_owned(),
+            });
+
+        let fetch = |request: RequestBuilder| -> Result<Vec<u8>> {
+            let res = self.send(request)?;
+            let url = res.url().to_string();
+            let status = res.status();
+            let text = res.text()?;
+
+            // Check response before it gets cached.
+            serde_json::from_str::<serde::de::IgnoredAny>(&text)
+                .with_context(|| format!("Unexpected response ({}) from {}", status, url))?;
+            Ok(text.into_bytes())
+        };
+
+        let data = match key {
+            Some(key) => self.cached("reddit", &key, Some(self.reddit_ttl), || fetch(request))?,
+            None => fetch(request)?,
+        };
+
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    pub fn bytes(&self, request: RequestBuilder) -> Result<Vec<u8>> {
+        Ok(self.send(request)?.bytes()?.to_vec())
+    }
+
+    fn token(&self, oauth: &OAuth) -> Result<String> {
+        let mut token = self.token.lock().unwrap();
+
+        // Refresh token a minute before it expires.
+        if let Some(token) = token
+            .as_ref()
+            .filter(|x| x.expires_at > Instant::now() + Duration::from_secs(60))
+        {
+            return Ok(token.access_token.clone());
+        }
+
+        let form = match (&oauth.username, &oauth.password) {
+            (Some(username), Some(password)) => vec![
+                ("grant_type", "password"),
+                ("username", username.as_str()),
+                ("password", password.as_str()),
+            ],
+            _ => vec![("grant_type", "client_credentials")],
+        };
+
+        let res = self
+            .json::<TokenResponse>(
+                self.client
+                    .post(&oauth.token_url)
+                    .basic_auth(&oauth.client_id, Some(&oauth.client_secret))
+                    .form(&form),
+            )
+            .context("Couldn't fetch reddit oauth token")?;
+
+        let access_token = match (res.access_token, res.error) {
+            (Some(access_token), _) => access_token,
+            (None, error) => bail!(
+                "Couldn't fetch reddit oauth token: {}",
+                error.unwrap_or_else(|| "no access token in response".to_owned())
+            ),
+        };
+
+        *token = Some(Token {
+            access_token: access_token.clone(),
+            expires_at: Instant::now() + Duration::from_secs(res.expires_in.unwrap_or(3600)),
+        });
+        Ok(access_token)
+    }
+
+    fn wait(&self) {
+        let wait_until = *self.wait_until.lock().unwrap();
+
+        if let Some(wait_until) = wait_until {
+            let now = Instant::now();
+
+            if wait_until > now {
+                std::thread::sleep(wait_until - now);
+            }
+        }
+    }
+
+    /// Reddit reports remaining requests and seconds until reset of the rate limit window.
+    fn update_rate_limit(&self, res: &Response) {
+        let header = |name: &str| {
+            res.headers()
+                .get(name)
+                .and_then(|x| x.to_str().ok())
+                .and_then(|x| x.trim().parse::<f64>().ok())
+        };
+
+        if let (Some(remaining), Some(reset)) =
+            (header("x-ratelimit-remaining"), header("x-ratelimit-reset"))
+        {
+            *self.wait_until.lock().unwrap() = if remaining < 1.0 {
+                Some(Instant::now() + Duration::from_secs_f64(reset.max(0.0)))
+            } else {
+                None
+            };
+        }
+    }
+}
+
+fn retry_after(res: &Response) -> Option<Duration> {
+    res.headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    use super::{Client, OAuth};
+
+    /// Serve canned `responses` (status and body), one for each connection, on a local port.
+    /// Returns base url of server and a handle which yields requests received by server.
+    pub(crate) fn serve(responses: Vec<(u16, Vec<u8>)>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let mut requests = vec![];
+
+            for (status, response) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                let mut length = 0;
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+
+                    request.push_str(&line);
+
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                }
+
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8_lossy(&body));
+                requests.push(request);
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Stand-in\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    response.len()
+                )
+                .unwrap();
+                stream.write_all(&response).unwrap();
+            }
+
+            requests
+        });
+
+        (url, handle)
+    }
+
+    fn oauth(token_url: &str) -> OAuth {
+        OAuth {
+            client_id: "id".to_owned(),
+            client_secret: "secret".to_owned(),
+            username: Some("user".to_owned()),
+            password: Some("pass".to_owned()),
+            token_url: token_url.to_owned(),
+        }
+    }
+
+    #[test]
+    fn oauth_token() {
+        let (url, server) = serve(vec![(
+            200,
+            br#"{"access_token": "abc", "token_type": "bearer", "expires_in": 86400}"#.to_vec(),
+        )]);
+        let client = Client::new("test")
+            .unwrap()
+            .with_oauth(oauth(&format!("{}/api/v1/access_token", url)));
+
+        // Token is fetched once and reused for later requests.
+        for _ in 0..2 {
+            let request = client
+                .reddit("/r/AskReddit/hot.json")
+                .unwrap()
+                .build()
+                .unwrap();
+            assert_eq!(
+                request.url().as_str(),
+                "https://oauth.reddit.com/r/AskReddit/hot.json"
+            );
+            assert_eq!(request.headers()["authorization"], "Bearer abc");
+        }
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("POST /api/v1/access_token "));
+        // base64 of id:secret
+        assert!(requests[0].contains("Basic aWQ6c2VjcmV0"));
+        assert!(requests[0].ends_with("grant_type=password&username=user&password=pass"));
+    }
+
+    #[test]
+    fn oauth_token_error() {
+        let (url, server) = serve(vec![(200, br#"{"error": "invalid_grant"}"#.to_vec())]);
+        let client = Client::new("test")
+            .unwrap()
+            .with_oauth(oauth(&format!("{}/api/v1/access_token", url)));
+
+        let error = client.reddit("/r/AskReddit/hot.json").unwrap_err();
+        assert!(error.to_string().contains("invalid_grant"));
+        server.join().unwrap();
+    }
+}
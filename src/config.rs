@@ -0,0 +1,150 @@
+use std::io::Read;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::client::OAuth;
+use crate::subreddit::Blocklist;
+use crate::tts::{Espeak, Google, Http, Lexicon, Mask, MaskMode, Piper, Registry, GOOGLE_URL};
+
+/// Configuration file which is looked up as redtake.toml in current directory
+/// when not given explicitly.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub reddit: Reddit,
+    pub filter: Filter,
+    pub tts: Tts,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Reddit {
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Filter {
+    pub words: Vec<String>,
+    pub patterns: Vec<String>,
+    pub mask: MaskMode,
+    pub profanity: Vec<String>,
+    pub replacement: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Tts {
+    pub lexicon: Option<String>,
+    pub google_url: String,
+    pub espeak_command: String,
+    pub piper_command: String,
+    pub piper_models: Vec<String>,
+    pub http: Vec<Http>,
+}
+
+impl Default for Tts {
+    fn default() -> Self {
+        Self {
+            lexicon: None,
+            google_url: GOOGLE_URL.to_owned(),
+            espeak_command: "espeak-ng".to_owned(),
+            piper_command: "piper".to_owned(),
+            piper_models: vec![],
+            http: vec![],
+        }
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            words: vec![],
+            patterns: vec![],
+            mask: MaskMode::Off,
+            profanity: vec![],
+            replacement: "beep".to_owned(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load_toml(path: Option<&str>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None if std::path::Path::new("redtake.toml").exists() => "redtake.toml",
+            None => return Ok(Self::default()),
+        };
+
+        let mut config = String::new();
+        std::fs::File::open(path)
+            .with_context(|| format!("Couldn't open config file {}", path))?
+            .read_to_string(&mut config)?;
+        toml::from_str::<Self>(&config)
+            .with_context(|| format!("Couldn't parse config file {}", path))
+    }
+}
+
+impl Reddit {
+    /// OAuth credentials from REDTAKE_* environment variables or else from config file.
+    /// Password grant is used when username and password are both present.
+    pub fn oauth(&self) -> Option<OAuth> {
+        let var = |name: &str, value: &Option<String>| {
+            std::env::var(name)
+                .ok()
+                .or_else(|| value.clone())
+                .filter(|x| !x.is_empty())
+        };
+
+        Some(OAuth {
+            client_id: var("REDTAKE_CLIENT_ID", &self.client_id)?,
+            client_secret: var("REDTAKE_CLIENT_SECRET", &self.client_secret).unwrap_or_default(),
+            username: var("REDTAKE_USERNAME", &self.username),
+            password: var("REDTAKE_PASSWORD", &self.password),
+            token_url: var("REDTAKE_TOKEN_URL", &self.token_url)
+                .unwrap_or_else(|| "https://www.reddit.com/api/v1/access_token".to_owned()),
+        })
+    }
+}
+
+impl Filter {
+    pub fn blocklist(&self) -> Result<Blocklist> {
+        Blocklist::new(&self.words, &self.patterns)
+    }
+
+    pub fn mask(&self) -> Result<Mask> {
+        Mask::new(self.mask.clone(), &self.profanity, &self.replacement)
+    }
+}
+
+impl Tts {
+    pub fn lexicon(&self) -> Result<Lexicon> {
+        Lexicon::load_toml(self.lexicon.as_deref().filter(|x| !x.is_empty()))
+    }
+
+    /// Registry of all tts providers, configured by this section.
+    pub fn registry(&self, proxy: bool) -> Result<Registry> {
+        let mut registry = Registry::new(proxy);
+        registry.register(Google::new(&self.google_url));
+        registry.register(Espeak::new(&self.espeak_command));
+
+        if !self.piper_models.is_empty() {
+            registry.register(Piper::new(&self.piper_command, &self.piper_models)?);
+        }
+
+        for (i, http) in self.http.iter().enumerate() {
+            if http.url.is_empty() {
+                bail!("Url of [[tts.http]] entry {} is not set.", i + 1);
+            }
+
+            registry.register(http.clone());
+        }
+
+        Ok(registry.with_lexicon(self.lexicon()?))
+    }
+}
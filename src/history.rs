@@ -0,0 +1,127 @@
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Project created by redtake, stored as a line of history.jsonl.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Entry {
+    pub thread: String,
+    /// Starting comment of projects created from comment permalinks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub comments: Vec<String>,
+    pub project: String,
+    pub created: u64,
+}
+
+/// History of threads and comments used in projects,
+/// so that same thread isn't made again after its project is moved or deleted.
+pub struct History {
+    path: PathBuf,
+    pub entries: Vec<Entry>,
+}
+
+impl History {
+    /// $XDG_DATA_HOME/redtake/history.jsonl or ~/.local/share/redtake/history.jsonl
+    pub fn default_path() -> String {
+        crate::cache::redtake_dir("XDG_DATA_HOME", ".local/share")
+            .join("history.jsonl")
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let path = PathBuf::from(path);
+        let mut entries = vec![];
+
+        if path.exists() {
+            let file = std::fs::File::open(&path)?;
+
+            for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
+                let line = line?;
+
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                entries.push(serde_json::from_str(&line).with_context(|| {
+                    format!("Couldn't parse line {} of {}", i + 1, path.display())
+                })?);
+            }
+        }
+
+        Ok(Self { path, entries })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = std::fs::File::create(&self.path)?;
+
+        for entry in &self.entries {
+            writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn contains(&self, thread: &str, comment: Option<&str>) -> bool {
+        self.entries
+            .iter()
+            .any(|x| x.thread == thread && x.comment.as_deref() == comment)
+    }
+
+    /// Comments of thread already narrated in other projects,
+    /// except the starting `comment` which is always narrated.
+    pub fn used_comments(&self, thread: &str, comment: Option<&str>) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|x| x.thread == thread && x.comment.as_deref() != comment)
+            .flat_map(|x| x.comments.clone())
+            .filter(|x| Some(x.as_str()) != comment)
+            .collect()
+    }
+
+    /// Record project replacing any earlier project of same thread and starting comment.
+    pub fn add(
+        &mut self,
+        thread: &str,
+        comment: Option<&str>,
+        comments: Vec<String>,
+        project: &str,
+    ) -> Result<()> {
+        self.entries
+            .retain(|x| !(x.thread == thread && x.comment.as_deref() == comment));
+        self.entries.push(Entry {
+            thread: thread.to_owned(),
+            comment: comment.map(|x| x.to_owned()),
+            comments,
+            project: project.to_owned(),
+            created: now(),
+        });
+        self.save()
+    }
+
+    /// Remove entries matching `predicate` and return number of removed entries.
+    pub fn prune<F>(&mut self, predicate: F) -> Result<usize>
+    where
+        F: Fn(&Entry) -> bool,
+    {
+        let len = self.entries.len();
+        self.entries.retain(|x| !predicate(x));
+        self.save()?;
+        Ok(len - self.entries.len())
+    }
+}
+
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,367 @@
+use anyhow::{bail, Result};
+
+/// Sources from which redtake can generate projects.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    /// Subreddit name without r/ prefix.
+    /// Multiple subreddits are joined by +.
+    Subreddit(String),
+    Thread {
+        subreddit: Option<String>,
+        id: String,
+    },
+    Comment {
+        subreddit: Option<String>,
+        thread_id: String,
+        id: String,
+    },
+    /// Username without u/ prefix.
+    User(String),
+    Multireddit {
+        user: String,
+        name: String,
+    },
+    Search {
+        subreddit: Option<String>,
+        query: String,
+    },
+    /// Share links (reddit.com/r/.../s/...) which only redirect to the real thread.
+    Share(String),
+    /// Local json dump or directory of dumps.
+    File(std::path::PathBuf),
+}
+
+impl Input {
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        let (input, query) = match input.split_once('?') {
+            Some((input, query)) => (input, Some(query)),
+            None => (input, None),
+        };
+        let input = input.split('#').next().unwrap();
+
+        let mut path = input
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+
+        // Short links (redd.it/abc123) points directly to a thread.
+        if let Some(id) = path
+            .strip_prefix("redd.it/")
+            .or_else(|| path.strip_prefix("www.redd.it/"))
+        {
+            return Ok(Self::Thread {
+                subreddit: None,
+                id: id.trim_end_matches('/').to_owned(),
+            });
+        }
+
+        // Strip any reddit host (www., old., new., np., m. etc.).
+        if let Some((host, rest)) = path.split_once('/') {
+            if host == "reddit.com" || host.ends_with(".reddit.com") {
+                path = rest;
+            }
+        } else if path == "reddit.com" || path.ends_with(".reddit.com") {
+            path = "";
+        }
+
+        let mut segments = path
+            .split('/')
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<_>>();
+
+        // Json endpoints like comments/vus3ch.json
+        if let Some(last) = segments.last_mut() {
+            *last = last.strip_suffix(".json").unwrap_or(last);
+        }
+
+        let search_query = query.and_then(|query| {
+            query
+                .split('&')
+                .filter_map(|x| x.split_once('='))
+                .find(|(key, _)| *key == "q")
+                .map(|(_, value)| decode_query(value))
+        });
+
+        match segments.as_slice() {
+            ["r", subreddit, "comments", thread_id, _, id, ..] if is_id(thread_id) && is_id(id) => {
+                Ok(Self::Comment {
+                    subreddit: Some(subreddit.to_string()),
+                    thread_id: thread_id.to_string(),
+                    id: id.to_string(),
+                })
+            }
+            ["r", subreddit, "comments", id, ..] if is_id(id) => Ok(Self::Thread {
+                subreddit: Some(subreddit.to_string()),
+                id: id.to_string(),
+            }),
+            ["r", _, "comments", ..] => bail!("Unsupported reddit input: {}", input),
+            ["r", _, "s", _] => Ok(Self::Share(format!("https://www.reddit.com/{}", path))),
+            ["r", subreddit, "search", ..] => Ok(Self::Search {
+                subreddit: Some(subreddit.to_string()),
+                query: search_query.unwrap_or_default(),
+            }),
+            ["r", subreddit, ..] => Ok(Self::Subreddit(subreddit.to_string())),
+            ["comments", thread_id, _, id, ..] if is_id(thread_id) && is_id(id) => {
+                Ok(Self::Comment {
+                    subreddit: None,
+                    thread_id: thread_id.to_string(),
+                    id: id.to_string(),
+                })
+            }
+            ["comments" | "gallery", id, ..] if is_id(id) => Ok(Self::Thread {
+                subreddit: None,
+                id: id.to_string(),
+            }),
+            ["comments" | "gallery", ..] => bail!("Unsupported reddit input: {}", input),
+            ["u" | "user", user, "m", name, ..] => Ok(Self::Multireddit {
+                user: user.to_string(),
+                name: name.to_string(),
+            }),
+            ["u" | "user", user, ..] => Ok(Self::User(user.to_string())),
+            ["search", ..] => Ok(Self::Search {
+                subreddit: None,
+                query: search_query.unwrap_or_default(),
+            }),
+            // Bare thread ids like vus3ch or t3_vus3ch.
+            [id] if id.starts_with("t3_") && is_id(&id[3..]) => Ok(Self::Thread {
+                subreddit: None,
+                id: id[3..].to_owned(),
+            }),
+            [id] if is_id(id) && id.contains(|x: char| x.is_ascii_digit()) => Ok(Self::Thread {
+                subreddit: None,
+                id: id.to_string(),
+            }),
+            // Ids without digits can just as well be a subreddit name.
+            [id] if is_id(id) => bail!(
+                "{} is ambiguous, use r/{} for a subreddit or t3_{} for a thread id.",
+                id,
+                id,
+                id
+            ),
+            _ => bail!("Unsupported reddit input: {}", input),
+        }
+    }
+}
+
+impl std::str::FromStr for Input {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+fn is_id(text: &str) -> bool {
+    !text.is_empty()
+        && text.len() <= 10
+        && text
+            .chars()
+            .all(|x| x.is_ascii_digit() || x.is_ascii_lowercase())
+}
+
+/// Decode percent encoded query value.
+fn decode_query(value: &str) -> String {
+    let value = value.replace('+', " ");
+    let bytes = value.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(byte) = std::str::from_utf8(&bytes[i + 1..i + 3])
+                .ok()
+                .and_then(|x| u8::from_str_radix(x, 16).ok())
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Input;
+
+    fn thread(subreddit: Option<&str>, id: &str) -> Input {
+        Input::Thread {
+            subreddit: subreddit.map(|x| x.to_owned()),
+            id: id.to_owned(),
+        }
+    }
+
+    fn comment(subreddit: Option<&str>, thread_id: &str, id: &str) -> Input {
+        Input::Comment {
+            subreddit: subreddit.map(|x| x.to_owned()),
+            thread_id: thread_id.to_owned(),
+            id: id.to_owned(),
+        }
+    }
+
+    fn search(subreddit: Option<&str>, query: &str) -> Input {
+        Input::Search {
+            subreddit: subreddit.map(|x| x.to_owned()),
+            query: query.to_owned(),
+        }
+    }
+
+    fn parse(input: &str) -> Input {
+        Input::parse(input).unwrap()
+    }
+
+    #[test]
+    fn short_links() {
+        assert_eq!(parse("https://redd.it/vus3ch"), thread(None, "vus3ch"));
+        assert_eq!(parse("redd.it/vus3ch/"), thread(None, "vus3ch"));
+        assert_eq!(parse("https://www.redd.it/vus3ch"), thread(None, "vus3ch"));
+    }
+
+    #[test]
+    fn hosts() {
+        for host in [
+            "reddit.com",
+            "www.reddit.com",
+            "old.reddit.com",
+            "np.reddit.com",
+            "m.reddit.com",
+        ] {
+            assert_eq!(
+                parse(&format!(
+                    "https://{}/r/AskReddit/comments/vus3ch/what_hits/",
+                    host
+                )),
+                thread(Some("AskReddit"), "vus3ch")
+            );
+        }
+
+        assert_eq!(
+            parse("http://old.reddit.com/r/AskReddit"),
+            Input::Subreddit("AskReddit".to_owned())
+        );
+    }
+
+    #[test]
+    fn share_links() {
+        assert_eq!(
+            parse("https://www.reddit.com/r/AskReddit/s/AbC123xyz"),
+            Input::Share("https://www.reddit.com/r/AskReddit/s/AbC123xyz".to_owned())
+        );
+        assert_eq!(
+            parse("reddit.com/r/AskReddit/s/AbC123xyz?utm_source=share"),
+            Input::Share("https://www.reddit.com/r/AskReddit/s/AbC123xyz".to_owned())
+        );
+    }
+
+    #[test]
+    fn bare_ids() {
+        assert_eq!(parse("vus3ch"), thread(None, "vus3ch"));
+        assert_eq!(parse("t3_vus3ch"), thread(None, "vus3ch"));
+        assert_eq!(parse("t3_abcdef"), thread(None, "abcdef"));
+        assert!(Input::parse("askreddit")
+            .unwrap_err()
+            .to_string()
+            .contains("ambiguous"));
+        assert!(Input::parse("notAnId").is_err());
+    }
+
+    #[test]
+    fn query_and_fragment() {
+        assert_eq!(
+            parse("https://www.reddit.com/r/AskReddit/comments/vus3ch/what_hits/?utm_source=share&utm_medium=web"),
+            thread(Some("AskReddit"), "vus3ch")
+        );
+        assert_eq!(
+            parse("https://www.reddit.com/r/AskReddit/comments/vus3ch/#comments"),
+            thread(Some("AskReddit"), "vus3ch")
+        );
+    }
+
+    #[test]
+    fn comment_permalinks() {
+        assert_eq!(
+            parse("https://www.reddit.com/r/AskReddit/comments/vus3ch/what_hits/ifb2x6e/"),
+            comment(Some("AskReddit"), "vus3ch", "ifb2x6e")
+        );
+        assert_eq!(
+            parse("https://www.reddit.com/r/AskReddit/comments/vus3ch/comment/ifb2x6e/?context=3"),
+            comment(Some("AskReddit"), "vus3ch", "ifb2x6e")
+        );
+        assert_eq!(
+            parse("reddit.com/comments/vus3ch/_/ifb2x6e"),
+            comment(None, "vus3ch", "ifb2x6e")
+        );
+    }
+
+    #[test]
+    fn json_endpoints_and_invalid_ids() {
+        assert_eq!(
+            parse("reddit.com/r/AskReddit/comments/vus3ch.json"),
+            thread(Some("AskReddit"), "vus3ch")
+        );
+        assert_eq!(
+            parse("https://www.reddit.com/comments/vus3ch/_/ifb2x6e.json?limit=5"),
+            comment(None, "vus3ch", "ifb2x6e")
+        );
+
+        for input in [
+            "https://www.reddit.com/r/AskReddit/comments/Not-An-Id/",
+            "reddit.com/comments/vus3ch.xml",
+            "reddit.com/gallery/",
+        ] {
+            assert!(Input::parse(input)
+                .unwrap_err()
+                .to_string()
+                .starts_with("Unsupported reddit input"));
+        }
+    }
+
+    #[test]
+    fn users_and_multireddits() {
+        assert_eq!(parse("u/spez"), Input::User("spez".to_owned()));
+        assert_eq!(
+            parse("https://www.reddit.com/user/spez/submitted/"),
+            Input::User("spez".to_owned())
+        );
+        assert_eq!(
+            parse("https://www.reddit.com/user/spez/m/news"),
+            Input::Multireddit {
+                user: "spez".to_owned(),
+                name: "news".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn subreddits() {
+        assert_eq!(
+            parse("r/AskReddit"),
+            Input::Subreddit("AskReddit".to_owned())
+        );
+        assert_eq!(
+            parse("r/AskReddit+NoStupidQuestions"),
+            Input::Subreddit("AskReddit+NoStupidQuestions".to_owned())
+        );
+        assert_eq!(
+            parse("https://www.reddit.com/r/AskReddit/top/?t=week"),
+            Input::Subreddit("AskReddit".to_owned())
+        );
+    }
+
+    #[test]
+    fn searches() {
+        assert_eq!(
+            parse("https://www.reddit.com/search/?q=first+job%21&sort=new"),
+            search(None, "first job!")
+        );
+        assert_eq!(
+            parse("https://www.reddit.com/r/AskReddit/search?q=first%20job&restrict_sr=1"),
+            search(Some("AskReddit"), "first job")
+        );
+    }
+}
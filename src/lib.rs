@@ -1,4 +1,9 @@
 pub mod args;
+pub mod cache;
+pub mod client;
+pub mod config;
+pub mod history;
+pub mod input;
 pub mod screenshot;
 pub mod tts;
 pub mod subreddit;
@@ -1,17 +1,51 @@
 use anyhow::{bail, Result};
-use clap::ArgEnum;
 use headless_chrome::{Browser, LaunchOptionsBuilder};
 
 use redtake::args::Commands;
 
 fn main() -> Result<()> {
     let args = redtake::args::parse();
+    let config = redtake::config::Config::load_toml(args.config.as_deref())?;
+    let mut client = redtake::client::Client::new(&args.user_agent)?;
+
+    if let Some(oauth) = config.reddit.oauth() {
+        client = client.with_oauth(oauth);
+    }
+
+    let blocklist = config.filter.blocklist()?;
+    let mask = config.filter.mask()?;
+    let cache_ttl = std::time::Duration::from_secs(args.cache_ttl * 60);
+
+    if !args.no_cache {
+        client = client.with_cache(
+            redtake::cache::Cache::new(
+                &args
+                    .cache_dir
+                    .unwrap_or_else(redtake::cache::Cache::default_dir),
+                args.offline,
+            ),
+            cache_ttl,
+        );
+    }
+
+    let history_path = args
+        .history_file
+        .unwrap_or_else(redtake::history::History::default_path);
 
     match args.command {
         Commands::New(new_args) => {
-            let client = reqwest::blocking::Client::new();
-            let reddit_threads =
-                redtake::subreddit::fetch(&client, &new_args.input, new_args.limit)?;
+            let mut history = redtake::history::History::load(&history_path)?;
+            let tts = config.tts.registry(new_args.tts_proxy)?;
+            tts.find(&new_args.voice)?;
+            let input = new_args.input()?;
+            let reddit_threads = redtake::subreddit::fetch(
+                &client,
+                &input,
+                new_args.limit,
+                &new_args.sort,
+                &new_args.search_sort,
+                new_args.time.as_ref(),
+            )?;
             let reddit_threads = reddit_threads
                 .iter()
                 .filter(|x| {
@@ -25,6 +59,14 @@ fn main() -> Result<()> {
 
                     true
                 })
+                .filter(|x| new_args.min_score.is_none_or(|min| x.score >= min))
+                .filter(|x| {
+                    new_args
+                        .min_comments
+                        .is_none_or(|min| x.num_comments >= min)
+                })
+                .filter(|x| new_args.max_age.is_none_or(|max| x.age() <= max))
+                .filter(|x| !x.is_blocked(&blocklist))
                 .collect::<Vec<_>>();
 
             let browser = Browser::new(
@@ -34,13 +76,36 @@ fn main() -> Result<()> {
                     .unwrap(),
             )?;
             let tab = redtake::screenshot::tab(&browser, !new_args.light)?;
+            let theme = if new_args.light { "light" } else { "dark" };
+            let listing_name = match input {
+                redtake::input::Input::Search { .. } => "Search".to_owned(),
+                redtake::input::Input::File(_) => "File".to_owned(),
+                _ => new_args.sort.name(),
+            };
 
             for (i, thread) in reddit_threads.iter().enumerate() {
+                if new_args.skip && history.contains(&thread.id, thread.comment.as_deref()) {
+                    println!("Skipped creating {} (present in history)", thread.id);
+                    continue;
+                }
+
                 // Directory Structure
+                // Projects starting from a comment are kept apart from thread's project.
+                let project_id = match &thread.comment {
+                    Some(comment) => format!("{} {}", thread.id, comment),
+                    None => thread.id.clone(),
+                };
+
                 let mut thread_dir = if reddit_threads.len() == 1 {
-                    format!("Project {} ({})", thread.id, thread.title)
+                    format!("Project {} ({})", project_id, thread.title)
                 } else {
-                    format!("Project {} Hot {} ({})", thread.id, i + 1, thread.title)
+                    format!(
+                        "Project {} {} {} ({})",
+                        thread.id,
+                        listing_name,
+                        i + 1,
+                        thread.title
+                    )
                 };
 
                 if std::path::Path::new(&thread_dir).exists() {
@@ -53,9 +118,9 @@ fn main() -> Result<()> {
 
                 if std::fs::create_dir(&thread_dir).is_err() {
                     thread_dir = if reddit_threads.len() == 1 {
-                        format!("Project {}", thread.id)
+                        format!("Project {}", project_id)
                     } else {
-                        format!("Project {} Hot {}", thread.id, i + 1)
+                        format!("Project {} {} {}", thread.id, listing_name, i + 1)
                     };
 
                     if std::path::Path::new(&thread_dir).exists() {
@@ -74,13 +139,15 @@ fn main() -> Result<()> {
                     dynamic_ncols = true
                 );
                 let mut video = redtake::video::Project::default();
+                let mut used_comments = vec![];
 
                 println!("Generating tts for thread title");
-                new_args.voice.save(
+                tts.save(
                     &client,
+                    &new_args.voice,
                     &format!("{}. {}", thread.title, thread.selftext),
                     &format!("{}/audio/title.mp3", thread_dir),
-                    new_args.tts_proxy,
+                    &mask,
                 )?;
                 let duration =
                     redtake::video::duration(&format!("{}/audio/title.mp3", thread_dir))?;
@@ -88,15 +155,35 @@ fn main() -> Result<()> {
                 pb.update(pb_update_factor);
 
                 pb.write("Taking screenshot of thread title".to_owned());
-                redtake::screenshot::take_title_screenshot(
-                    &tab,
-                    &thread,
-                    &format!("{}/images/title.png", thread_dir),
+                let image_path = format!("{}/images/title.png", thread_dir);
+                let image = client.cached(
+                    "screenshot",
+                    &format!("{} {}", thread.url(), theme),
+                    Some(cache_ttl),
+                    || {
+                        redtake::screenshot::take_title_screenshot(&tab, thread, &image_path)?;
+                        Ok(std::fs::read(&image_path)?)
+                    },
                 )?;
+                std::fs::write(&image_path, image)?;
                 pb.update(pb_update_factor);
 
                 video.add_overlay("images/title.png", "audio/title.mp3", duration);
 
+                if !thread.media.is_empty() {
+                    pb.write("Downloading media of thread".to_owned());
+                    let mut media = vec![];
+
+                    for path in thread.save_media(&client, &thread_dir) {
+                        match path {
+                            Ok(path) => media.push(path),
+                            Err(e) => pb.write(format!("Skipped media of thread: {:#}", e)),
+                        }
+                    }
+
+                    video.add_media(media);
+                }
+
                 if video.len() > new_args.max_duration {
                     pb.write(
                         "Thread content is of more duration than max length. \
@@ -104,52 +191,92 @@ fn main() -> Result<()> {
                             .to_owned(),
                     );
                 } else {
-                    let comments = thread.comments(&client, new_args.max_length)?;
+                    let mut comments = thread.comments(
+                        &client,
+                        new_args.max_length,
+                        new_args
+                            .depth
+                            .unwrap_or(if thread.comment.is_some() { 10 } else { 0 }),
+                        new_args.expand_more,
+                    )?;
+
+                    let excluded = if new_args.reuse_comments {
+                        vec![]
+                    } else {
+                        history.used_comments(&thread.id, thread.comment.as_deref())
+                    };
+                    comments = redtake::subreddit::filter_comments(comments, &|x| {
+                        !excluded.contains(&x.id) && !blocklist.is_match(&x.body)
+                    });
+
+                    let comments = new_args.comment_strategy.select(
+                        &client,
+                        comments,
+                        new_args.min_comment_score,
+                        new_args.max_duration - video.len(),
+                    )?;
                     let mut comment_count = 1;
 
-                    for comment in &comments {
-                        comment.save_json(&format!(
-                            "{}/data/comment_{}.json",
-                            thread_dir, comment_count
-                        ))?;
-                        let tts_path =
-                            format!("{}/audio/comment_{}.mp3", thread_dir, comment_count);
-                        pb.write(format!("Generating tts for {} comment.", comment_count));
-                        new_args.voice.save(
-                            &client,
-                            &comment.body,
-                            &tts_path,
-                            new_args.tts_proxy,
-                        )?;
-                        let duration = redtake::video::duration(&tts_path)?;
-                        let pb_update_factor = (duration * 500.0) as usize;
-                        pb.update(pb_update_factor);
-
-                        if video.len() + duration > new_args.max_duration {
-                            std::fs::remove_file(&tts_path)?;
-                            break;
-                        }
+                    'chains: for chain in comments.iter().map(|x| x.chain()) {
+                        for comment in chain {
+                            let tts_path =
+                                format!("{}/audio/comment_{}.mp3", thread_dir, comment_count);
+                            pb.write(format!("Generating tts for {} comment.", comment_count));
+                            tts.save(&client, &new_args.voice, &comment.body, &tts_path, &mask)?;
+                            let duration = redtake::video::duration(&tts_path)?;
+                            let pb_update_factor = (duration * 500.0) as usize;
+
+                            // Skip rest of this chain, later comments may still fit.
+                            if video.len() + duration > new_args.max_duration {
+                                std::fs::remove_file(&tts_path)?;
+                                continue 'chains;
+                            }
 
-                        pb.write(format!("Taking screenshot for {} comment.", comment_count));
-                        redtake::screenshot::take_comment_screenshot(
-                            &tab,
-                            &comment,
-                            &format!("{}/images/comment_{}.png", thread_dir, comment_count),
-                        )?;
-                        pb.update(pb_update_factor);
-
-                        video.add_overlay(
-                            &format!("images/comment_{}.png", comment_count),
-                            &format!("audio/comment_{}.mp3", comment_count),
-                            duration,
-                        );
-
-                        comment_count += 1;
+                            pb.update(pb_update_factor);
+                            used_comments.push(comment.id.clone());
+                            comment.save_json(&format!(
+                                "{}/data/comment_{}.json",
+                                thread_dir, comment_count
+                            ))?;
+
+                            pb.write(format!("Taking screenshot for {} comment.", comment_count));
+                            let image_path =
+                                format!("{}/images/comment_{}.png", thread_dir, comment_count);
+                            let image = client.cached(
+                                "screenshot",
+                                &format!("{} {}", comment.url(), theme),
+                                Some(cache_ttl),
+                                || {
+                                    redtake::screenshot::take_comment_screenshot(
+                                        &tab,
+                                        comment,
+                                        &image_path,
+                                    )?;
+                                    Ok(std::fs::read(&image_path)?)
+                                },
+                            )?;
+                            std::fs::write(&image_path, image)?;
+                            pb.update(pb_update_factor);
+
+                            video.add_overlay(
+                                &format!("images/comment_{}.png", comment_count),
+                                &format!("audio/comment_{}.mp3", comment_count),
+                                duration,
+                            );
+
+                            comment_count += 1;
+                        }
                     }
                 }
 
                 pb.write(format!("Saving {}/Project.toml", thread_dir));
                 video.save_toml(&format!("{}/Project.toml", thread_dir))?;
+                history.add(
+                    &thread.id,
+                    thread.comment.as_deref(),
+                    used_comments,
+                    &std::env::current_dir()?.join(&thread_dir).to_string_lossy(),
+                )?;
                 eprintln!();
             }
         }
@@ -167,21 +294,59 @@ fn main() -> Result<()> {
         }
 
         Commands::Tts(tts_args) => {
-            tts_args.voice.save(
-                &reqwest::blocking::Client::new(),
+            config.tts.registry(tts_args.tts_proxy)?.save(
+                &client,
+                &tts_args.voice,
                 &tts_args.text,
                 &tts_args.output,
-                tts_args.tts_proxy,
+                &mask,
             )?;
 
             if tts_args.duration {
                 println!("{}", redtake::video::duration(&tts_args.output)?);
             }
         }
+        Commands::History(history_args) => {
+            let mut history = redtake::history::History::load(&history_path)?;
+
+            if history_args.clear {
+                let removed = history.prune(|_| true)?;
+                println!("Removed {} entries", removed);
+            } else if !history_args.remove.is_empty() || history_args.older_than.is_some() {
+                let now = redtake::history::now();
+                let removed = history.prune(|x| {
+                    history_args.remove.contains(&x.thread)
+                        || history_args.older_than.is_some_and(|days| {
+                            now.saturating_sub(x.created) as f64 > days * 86400.0
+                        })
+                })?;
+                println!("Removed {} entries", removed);
+            } else {
+                let now = redtake::history::now();
+
+                for entry in &history.entries {
+                    let id = match &entry.comment {
+                        Some(comment) => format!("{} {}", entry.thread, comment),
+                        None => entry.thread.clone(),
+                    };
+
+                    println!(
+                        "{} ({:.1} days ago, {} comments) {}",
+                        id,
+                        now.saturating_sub(entry.created) as f64 / 86400.0,
+                        entry.comments.len(),
+                        entry.project
+                    );
+                }
+            }
+        }
         Commands::Voices => {
-            redtake::tts::Voices::value_variants()
+            config
+                .tts
+                .registry(false)?
+                .voices()
                 .iter()
-                .for_each(|x| println!("{}", x.to_possible_value().unwrap().get_name()));
+                .for_each(|x| println!("{}", x.name));
         }
     }
 
@@ -1,14 +1,558 @@
-use anyhow::{Context, Result};
-use serde::Serialize;
+use std::path::Path;
 
-#[derive(Debug, Serialize)]
+use anyhow::{bail, Context, Result};
+use clap::ArgEnum;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::client::Client;
+use crate::input::Input;
+
+#[derive(Debug, Clone, ArgEnum)]
+pub enum Sort {
+    Controversial,
+    Hot,
+    New,
+    Rising,
+    Top,
+}
+
+impl Sort {
+    pub fn name(&self) -> String {
+        let name = self.to_possible_value().unwrap().get_name().to_owned();
+        name[..1].to_uppercase() + &name[1..]
+    }
+}
+
+#[derive(Debug, Clone, ArgEnum)]
+pub enum SearchSort {
+    Comments,
+    Hot,
+    New,
+    Relevance,
+    Top,
+}
+
+#[derive(Debug, Clone, ArgEnum)]
+pub enum Time {
+    All,
+    Day,
+    Hour,
+    Month,
+    Week,
+    Year,
+}
+
+#[derive(Debug, Clone, ArgEnum)]
+pub enum CommentStrategy {
+    /// Comments in the order returned by reddit.
+    First,
+    /// Comments with highest score first.
+    Score,
+    /// Comments with highest score per character first.
+    ScorePerChar,
+    /// Comments whose authors have highest karma first.
+    AuthorKarma,
+    /// Comments which best fill the remaining video duration.
+    Fill,
+}
+
+impl CommentStrategy {
+    /// Select and order comments (along with their replies) for narration.
+    /// `budget` is the estimated duration (in seconds) available for comments.
+    pub fn select(
+        &self,
+        client: &Client,
+        comments: Vec<RedditThreadComment>,
+        min_score: Option<i64>,
+        budget: f32,
+    ) -> Result<Vec<RedditThreadComment>> {
+        let mut comments = match min_score {
+            Some(min_score) => prune_comments(comments, min_score),
+            None => comments,
+        };
+
+        match self {
+            Self::First => (),
+            Self::Score => comments.sort_by_key(|x| std::cmp::Reverse(x.score)),
+            Self::ScorePerChar => comments.sort_by(|a, b| {
+                b.score_per_char()
+                    .partial_cmp(&a.score_per_char())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            }),
+            Self::AuthorKarma => {
+                let mut karma = std::collections::HashMap::new();
+
+                for comment in &comments {
+                    if !karma.contains_key(&comment.author) {
+                        karma.insert(
+                            comment.author.clone(),
+                            author_karma(client, &comment.author)?,
+                        );
+                    }
+                }
+
+                comments.sort_by_key(|x| std::cmp::Reverse(karma[&x.author]));
+            }
+            Self::Fill => {
+                // 0/1 knapsack over comment chains with durations in tenths of a second.
+                let capacity = (budget.max(0.0) * 10.0) as usize;
+                let weights = comments
+                    .iter()
+                    .map(|x| (x.estimated_duration() * 10.0).ceil() as usize)
+                    .collect::<Vec<_>>();
+                let values = comments
+                    .iter()
+                    .map(|x| x.score.max(0) as u64 + 1)
+                    .collect::<Vec<_>>();
+                let selected = knapsack(&weights, &values, capacity);
+
+                comments = comments
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| selected.contains(i))
+                    .map(|(_, x)| x)
+                    .collect();
+            }
+        }
+
+        Ok(comments)
+    }
+}
+
+/// Drop comments (and their replies) scoring below `min_score`.
+fn prune_comments(comments: Vec<RedditThreadComment>, min_score: i64) -> Vec<RedditThreadComment> {
+    filter_comments(comments, &|x| x.score >= min_score)
+}
+
+/// Keep comments (along with their replies) for which `predicate` returns true.
+pub fn filter_comments<F>(
+    comments: Vec<RedditThreadComment>,
+    predicate: &F,
+) -> Vec<RedditThreadComment>
+where
+    F: Fn(&RedditThreadComment) -> bool,
+{
+    comments
+        .into_iter()
+        .filter(predicate)
+        .map(|mut x| {
+            x.replies = filter_comments(std::mem::take(&mut x.replies), predicate);
+            x
+        })
+        .collect()
+}
+
+/// Words and regular expressions which drop threads and comments matching them.
+#[derive(Debug, Default)]
+pub struct Blocklist {
+    regex: Option<regex::Regex>,
+}
+
+impl Blocklist {
+    /// Words are matched case insensitively as whole words and patterns are used as is.
+    pub fn new(words: &[String], patterns: &[String]) -> Result<Self> {
+        for pattern in patterns {
+            regex::Regex::new(pattern)
+                .with_context(|| format!("Invalid blocklist pattern {:?}", pattern))?;
+        }
+
+        let alternatives = words
+            .iter()
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| format!("(?i:{})", crate::tts::whole_word(x.trim(), "")))
+            .chain(patterns.iter().map(|x| format!("(?:{})", x)))
+            .collect::<Vec<_>>();
+
+        if alternatives.is_empty() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self {
+            regex: Some(regex::Regex::new(&alternatives.join("|"))?),
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.as_ref().is_some_and(|x| x.is_match(text))
+    }
+}
+
+/// Indices of items which maximize total value without exceeding capacity.
+fn knapsack(weights: &[usize], values: &[u64], capacity: usize) -> Vec<usize> {
+    let mut best = vec![vec![0_u64; capacity + 1]; weights.len() + 1];
+
+    for i in 1..=weights.len() {
+        for c in 0..=capacity {
+            best[i][c] = best[i - 1][c];
+
+            if weights[i - 1] <= c {
+                best[i][c] = best[i][c].max(best[i - 1][c - weights[i - 1]] + values[i - 1]);
+            }
+        }
+    }
+
+    let mut selected = vec![];
+    let mut c = capacity;
+
+    for i in (1..=weights.len()).rev() {
+        if best[i][c] != best[i - 1][c] {
+            selected.push(i - 1);
+            c -= weights[i - 1];
+        }
+    }
+
+    selected.reverse();
+    selected
+}
+
+fn author_karma(client: &Client, author: &str) -> Result<i64> {
+    if author.is_empty() || author == "[deleted]" {
+        return Ok(0);
+    }
+
+    let res = match client.json::<Thing>(client.reddit(&format!("/user/{}/about.json", author))?) {
+        Ok(res) => res,
+        // Profiles of shadowbanned and deleted accounts aren't found.
+        Err(e)
+            if e.downcast_ref::<reqwest::Error>()
+                .and_then(|x| x.status())
+                .is_some_and(|x| x == reqwest::StatusCode::NOT_FOUND) =>
+        {
+            return Ok(0)
+        }
+        Err(e) => return Err(e.context(format!("Couldn't fetch karma of u/{}", author))),
+    };
+
+    match res {
+        Thing::Account(account) => Ok(account.total_karma),
+        _ => Ok(0),
+    }
+}
+
+/// Reddit api wraps every object as `{"kind": ..., "data": {...}}`.
+#[derive(Debug)]
+enum Thing {
+    Listing(Listing),
+    Comment(Comment),
+    Link(Link),
+    More(More),
+    Account(Account),
+    Other,
+}
+
+impl<'de> Deserialize<'de> for Thing {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawThing {
+            kind: String,
+            #[serde(default)]
+            data: serde_json::Value,
+        }
+
+        let raw = RawThing::deserialize(deserializer)?;
+        let id = raw.data["id"].as_str().unwrap_or_default().to_owned();
+
+        let thing = match raw.kind.as_str() {
+            "Listing" => serde_json::from_value(raw.data).map(Thing::Listing),
+            "t1" => serde_json::from_value(raw.data).map(Thing::Comment),
+            "t3" => serde_json::from_value(raw.data).map(Thing::Link),
+            "more" => serde_json::from_value(raw.data).map(Thing::More),
+            "t2" => serde_json::from_value(raw.data).map(Thing::Account),
+            _ => Ok(Thing::Other),
+        };
+
+        // Name the object so that errors point to the exact thread or comment.
+        thing.map_err(|e| {
+            if id.is_empty() {
+                serde::de::Error::custom(format!("{}: {}", raw.kind, e))
+            } else {
+                serde::de::Error::custom(format!("{} {}: {}", raw.kind, id, e))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Listing {
+    #[serde(default)]
+    after: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    children: Vec<Thing>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Link {
+    #[serde(default, deserialize_with = "nullable")]
+    author: String,
+    #[serde(default, deserialize_with = "nullable")]
+    created_utc: f64,
+    #[serde(default)]
+    distinguished: Option<String>,
+    #[serde(default, deserialize_with = "edited")]
+    edited: Option<f64>,
+    #[serde(default, deserialize_with = "nullable")]
+    gallery_data: GalleryData,
+    #[serde(default, deserialize_with = "nullable")]
+    gilded: u64,
+    id: String,
+    #[serde(default, deserialize_with = "nullable")]
+    is_gallery: bool,
+    #[serde(default)]
+    link_flair_text: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    media_metadata: std::collections::HashMap<String, MediaMetadata>,
+    #[serde(default, deserialize_with = "nullable")]
+    num_comments: u64,
+    #[serde(default, deserialize_with = "nullable")]
+    over_18: bool,
+    permalink: String,
+    #[serde(default)]
+    post_hint: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    preview: Preview,
+    #[serde(default, deserialize_with = "nullable")]
+    score: i64,
+    #[serde(default, deserialize_with = "nullable")]
+    selftext: String,
+    #[serde(default, deserialize_with = "nullable")]
+    stickied: bool,
+    #[serde(default, deserialize_with = "nullable")]
+    subreddit: String,
+    title: String,
+    #[serde(default, deserialize_with = "nullable")]
+    total_awards_received: u64,
+    #[serde(default, deserialize_with = "nullable")]
+    upvote_ratio: f64,
+    #[serde(default)]
+    url_overridden_by_dest: Option<String>,
+}
+
+impl Link {
+    /// Urls of images of image and gallery posts or preview image of link and video posts.
+    fn media(&self) -> Vec<String> {
+        let urls = if self.is_gallery {
+            self.gallery_data
+                .items
+                .iter()
+                .filter_map(|x| self.media_metadata.get(&x.media_id))
+                .filter(|x| x.status.as_deref().is_none_or(|x| x == "valid"))
+                .filter_map(|x| x.s.as_ref())
+                .filter_map(|x| x.u.clone().or_else(|| x.gif.clone()))
+                .collect()
+        } else {
+            match self.post_hint.as_deref() {
+                None | Some("self") => vec![],
+                Some("image") => self.url_overridden_by_dest.iter().cloned().collect(),
+                Some(_) => self
+                    .preview
+                    .images
+                    .iter()
+                    .take(1)
+                    .map(|x| x.source.url.clone())
+                    .collect(),
+            }
+        };
+
+        // Urls in json responses are html escaped.
+        urls.into_iter().map(|x| x.replace("&amp;", "&")).collect()
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GalleryData {
+    #[serde(default, deserialize_with = "nullable")]
+    items: Vec<GalleryItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GalleryItem {
+    media_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MediaMetadata {
+    #[serde(default)]
+    status: Option<String>,
+    /// Source image
+    #[serde(default)]
+    s: Option<MediaSource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MediaSource {
+    #[serde(default)]
+    u: Option<String>,
+    /// Animated images have gif instead of u.
+    #[serde(default)]
+    gif: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Preview {
+    #[serde(default, deserialize_with = "nullable")]
+    images: Vec<PreviewImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PreviewImage {
+    source: PreviewSource,
+}
+
+#[derive(Debug, Deserialize)]
+struct PreviewSource {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Comment {
+    #[serde(default, deserialize_with = "nullable")]
+    author: String,
+    #[serde(default)]
+    author_flair_text: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    body: String,
+    #[serde(default, deserialize_with = "nullable")]
+    created_utc: f64,
+    #[serde(default)]
+    distinguished: Option<String>,
+    #[serde(default, deserialize_with = "edited")]
+    edited: Option<f64>,
+    #[serde(default, deserialize_with = "nullable")]
+    gilded: u64,
+    id: String,
+    #[serde(default, deserialize_with = "nullable")]
+    parent_id: String,
+    permalink: String,
+    #[serde(default, deserialize_with = "replies")]
+    replies: Vec<Thing>,
+    #[serde(default, deserialize_with = "nullable")]
+    score: i64,
+    #[serde(default, deserialize_with = "nullable")]
+    stickied: bool,
+    #[serde(default, deserialize_with = "nullable")]
+    total_awards_received: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Account {
+    #[serde(default, deserialize_with = "nullable")]
+    total_karma: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct More {
+    #[serde(default, deserialize_with = "nullable")]
+    children: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoreChildren {
+    json: MoreChildrenJson,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoreChildrenJson {
+    #[serde(default)]
+    errors: Vec<serde_json::Value>,
+    data: Option<MoreChildrenData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoreChildrenData {
+    #[serde(default, deserialize_with = "nullable")]
+    things: Vec<Thing>,
+}
+
+/// Treat `null` same as an absent field.
+fn nullable<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Edited is `false` for unedited objects otherwise timestamp of last edit.
+fn edited<'de, D>(deserializer: D) -> std::result::Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<serde_json::Value>::deserialize(deserializer)?.and_then(|x| x.as_f64()))
+}
+
+/// Comments without any reply have `"replies": ""` instead of a listing.
+fn replies<'de, D>(deserializer: D) -> std::result::Result<Vec<Thing>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(value) if value.is_object() => {
+            match Thing::deserialize(value).map_err(serde::de::Error::custom)? {
+                Thing::Listing(listing) => Ok(listing.children),
+                _ => Ok(vec![]),
+            }
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct RedditThreadComment {
+    #[serde(default)]
+    pub author: String,
+    pub author_flair_text: Option<String>,
     pub body: String,
-    id: String,
+    #[serde(default)]
+    pub created_utc: f64,
+    #[serde(default)]
+    pub depth: u8,
+    pub distinguished: Option<String>,
+    pub edited: Option<f64>,
+    #[serde(default)]
+    pub gilded: u64,
+    pub id: String,
     permalink: String,
+    #[serde(default)]
+    pub score: i64,
+    #[serde(default)]
+    pub total_awards_received: u64,
+    #[serde(skip)]
+    pub replies: Vec<RedditThreadComment>,
 }
 
 impl RedditThreadComment {
+    /// Comment followed by its replies in depth first order,
+    /// which is the order in which a parent→reply chain is narrated.
+    pub fn chain(&self) -> Vec<&RedditThreadComment> {
+        let mut chain = vec![self];
+
+        for reply in &self.replies {
+            chain.extend(reply.chain());
+        }
+
+        chain
+    }
+
+    /// Estimated tts duration (in seconds) of comment along with its replies.
+    pub fn estimated_duration(&self) -> f32 {
+        self.chain()
+            .iter()
+            .map(|x| crate::tts::estimate_duration(&crate::tts::normalize(&x.body)))
+            .sum()
+    }
+
+    fn score_per_char(&self) -> f32 {
+        let chars = self
+            .chain()
+            .iter()
+            .map(|x| x.body.chars().count())
+            .sum::<usize>();
+        self.score as f32 / chars.max(1) as f32
+    }
+
     pub fn url(&self) -> String {
         format!("https://reddit.com{}", self.permalink)
     }
@@ -24,36 +568,90 @@ impl RedditThreadComment {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct RedditThread {
+    #[serde(default)]
+    pub author: String,
+    /// Comment from which narration starts when input is a comment permalink.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub created_utc: f64,
+    pub distinguished: Option<String>,
+    pub edited: Option<f64>,
+    #[serde(default)]
+    pub gilded: u64,
     pub id: String,
+    pub link_flair_text: Option<String>,
+    /// Urls of images shown while title is narrated.
+    #[serde(default)]
+    pub media: Vec<String>,
+    #[serde(default)]
+    pub num_comments: u64,
     pub over_18: bool,
-    // num_comments: u64,
     permalink: String,
-    // score: i64,
+    #[serde(default)]
+    pub score: i64,
     pub selftext: String,
+    /// Subreddit (without r/ prefix) to which thread belongs,
+    /// which may differ for each thread when fetched from multiple subreddits.
+    #[serde(default)]
+    pub subreddit: String,
     pub title: String,
-    // upvote_ratio: f64,
+    #[serde(default)]
+    pub total_awards_received: u64,
+    #[serde(default)]
+    pub upvote_ratio: f64,
+    /// Comments loaded from local dumps instead of fetching them.
+    #[serde(skip)]
+    things: Option<Vec<Comment>>,
 }
 
 impl RedditThread {
-    pub fn new(child: &serde_json::Value) -> Option<Self> {
-        if child["data"]["stickied"].as_bool().unwrap() {
+    fn new(link: Link) -> Option<Self> {
+        if link.stickied {
             return None;
         }
 
+        let media = link.media();
+
         Some(Self {
-            id: child["data"]["id"].as_str().unwrap().to_owned(),
-            over_18: child["data"]["over_18"].as_bool().unwrap(),
-            // num_comments: child["data"]["num_comments"].as_u64().unwrap(),
-            permalink: child["data"]["permalink"].as_str().unwrap().to_owned(),
-            // score: child["data"]["score"].as_i64().unwrap(),
-            selftext: child["data"]["selftext"].as_str().unwrap().to_owned(),
-            title: child["data"]["title"].as_str().unwrap().to_owned(),
-            // upvote_ratio: child["data"]["upvote_ratio"].as_f64().unwrap(),
+            author: link.author,
+            comment: None,
+            created_utc: link.created_utc,
+            distinguished: link.distinguished,
+            edited: link.edited,
+            gilded: link.gilded,
+            id: link.id,
+            link_flair_text: link.link_flair_text,
+            media,
+            num_comments: link.num_comments,
+            over_18: link.over_18,
+            permalink: link.permalink,
+            score: link.score,
+            selftext: link.selftext,
+            subreddit: link.subreddit,
+            title: link.title,
+            total_awards_received: link.total_awards_received,
+            upvote_ratio: link.upvote_ratio,
+            things: None,
         })
     }
 
+    /// Title or text of thread matches blocklist.
+    pub fn is_blocked(&self, blocklist: &Blocklist) -> bool {
+        blocklist.is_match(&self.title) || blocklist.is_match(&self.selftext)
+    }
+
+    /// Age of thread in hours.
+    pub fn age(&self) -> f64 {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        (now - self.created_utc) / 3600.0
+    }
+
     pub fn url(&self) -> String {
         format!("https://reddit.com{}", self.permalink)
     }
@@ -63,104 +661,532 @@ impl RedditThread {
         Ok(())
     }
 
+    /// Download media of thread into images/ directory of project `dir`.
+    /// Each media is downloaded independently, so that a removed or expired image
+    /// doesn't prevent others from being used. Returned paths are relative to project directory.
+    pub fn save_media(&self, client: &Client, dir: &str) -> Vec<Result<String>> {
+        self.media
+            .iter()
+            .enumerate()
+            .map(|(i, url)| {
+                let extension = url
+                    .split(['?', '#'])
+                    .next()
+                    .and_then(|x| x.rsplit_once('.'))
+                    .map(|x| x.1.to_lowercase())
+                    .filter(|x| ["gif", "jpeg", "jpg", "png", "webp"].contains(&x.as_str()))
+                    .unwrap_or_else(|| "jpg".to_owned());
+                let path = format!("images/media_{}.{}", i + 1, extension);
+                let image = client
+                    .cached("media", url, None, || client.bytes(client.get(url)))
+                    .with_context(|| format!("Couldn't download media {}", url))?;
+                std::fs::write(format!("{}/{}", dir, path), image)?;
+                Ok(path)
+            })
+            .collect()
+    }
+
+    /// Fetch comments of thread as a tree.
+    /// Replies are descended upto `depth` levels below top level comments
+    /// and "more" stubs are expanded using morechildren endpoint when `expand_more` is true.
+    /// If thread has a starting comment then only that comment along with its replies is returned.
     pub fn comments(
         &self,
-        client: &reqwest::blocking::Client,
+        client: &Client,
         max_length: usize,
+        depth: u8,
+        expand_more: bool,
     ) -> Result<Vec<RedditThreadComment>> {
-        let mut comments = vec![];
+        let fetched;
+        let things = match &self.things {
+            Some(things) => things,
+            None => {
+                fetched = self.fetch_comments(client, depth, expand_more)?;
+                &fetched
+            }
+        };
+
+        if let Some(comment) = &self.comment {
+            let thing = things
+                .iter()
+                .find(|x| &x.id == comment)
+                .with_context(|| format!("Comment {} not found in thread {}", comment, self.id))?;
+
+            // Starting comment was asked for explicitly, so it isn't dropped silently.
+            if thing.body.contains("[removed]") || thing.body.contains("[deleted]") {
+                bail!(
+                    "Comment {} of thread {} is removed or deleted.",
+                    comment,
+                    self.id
+                );
+            } else if thing.stickied {
+                bail!("Comment {} of thread {} is stickied.", comment, self.id);
+            } else if thing.body.len() > max_length {
+                bail!(
+                    "Comment {} of thread {} is longer ({} characters) than max length ({}), \
+                    use --max-length to allow it.",
+                    comment,
+                    self.id,
+                    thing.body.len(),
+                    max_length
+                );
+            }
+
+            let mut comments = comment_tree(things, &thing.parent_id, 0, depth, max_length);
+            comments.retain(|x| &x.id == comment);
+            return Ok(comments);
+        }
+
+        Ok(comment_tree(
+            things,
+            &format!("t3_{}", self.id),
+            0,
+            depth,
+            max_length,
+        ))
+    }
+
+    fn fetch_comments(
+        &self,
+        client: &Client,
+        depth: u8,
+        expand_more: bool,
+    ) -> Result<Vec<Comment>> {
+        let path = match &self.comment {
+            Some(comment) => format!("/comments/{}/_/{}.json", self.id, comment),
+            None => format!("{}.json", self.permalink),
+        };
         let res = client
-            .get(&format!(
-                "https://reddit.com{}.json?depth=0",
-                self.permalink
-            ))
-            .send()?
-            .json::<serde_json::Value>()?;
-
-        for child in res.as_array().unwrap()[1]["data"]["children"]
-            .as_array()
-            .unwrap()
-        {
-            if child["kind"].as_str().unwrap() == "more" {
-                continue;
+            .json::<Vec<Thing>>(
+                client
+                    .reddit(&path)?
+                    .query(&[("depth", u16::from(depth) + 1)]),
+            )
+            .with_context(|| format!("Couldn't parse comments of thread {}", self.id))?;
+
+        let children = match res.into_iter().nth(1) {
+            Some(Thing::Listing(listing)) => listing.children,
+            _ => bail!("Comments listing is missing for thread {}", self.id),
+        };
+
+        let mut things = vec![];
+        let mut more = vec![];
+        flatten_comments(children, &mut things, &mut more);
+
+        if expand_more {
+            let mut expanded = std::collections::HashSet::new();
+
+            while !more.is_empty() {
+                let ids = more
+                    .drain(..more.len().min(100))
+                    .filter(|x| expanded.insert(x.clone()))
+                    .collect::<Vec<_>>();
+
+                if ids.is_empty() {
+                    continue;
+                }
+
+                let res = client
+                    .json::<MoreChildren>(client.reddit("/api/morechildren.json")?.query(&[
+                        ("api_type", "json"),
+                        ("link_id", &format!("t3_{}", self.id)),
+                        ("children", &ids.join(",")),
+                        ("limit_children", "false"),
+                    ]))
+                    .with_context(|| {
+                        format!("Couldn't expand more comments of thread {}", self.id)
+                    })?;
+
+                if !res.json.errors.is_empty() {
+                    bail!(
+                        "Couldn't expand more comments of thread {}: {:?}",
+                        self.id,
+                        res.json.errors
+                    );
+                }
+
+                if let Some(data) = res.json.data {
+                    flatten_comments(data.things, &mut things, &mut more);
+                }
             }
+        }
 
-            let body = child["data"]["body"].as_str().unwrap();
-            let id = child["data"]["id"].as_str().unwrap();
-            let permalink = child["data"]["permalink"].as_str().unwrap();
+        Ok(things)
+    }
+}
 
-            if child["data"]["stickied"].as_bool().unwrap()
-                || body.contains("[removed]")
-                || body.contains("[deleted]")
-                || body.len() > max_length
-            {
-                continue;
+/// Load threads from local json dumps.
+/// Path can be a reddit thread page or listing json, thread.json saved by redtake
+/// (comments are read from comment_N.json files next to it) or a directory of such dumps.
+pub fn load(path: &Path) -> Result<Vec<RedditThread>> {
+    if path.is_dir() {
+        for thread_json in [
+            path.join("thread.json"),
+            path.join("data").join("thread.json"),
+        ] {
+            if thread_json.exists() {
+                return load(&thread_json);
             }
+        }
+
+        let mut entries = std::fs::read_dir(path)?
+            .map(|x| x.map(|x| x.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort();
 
-            comments.push(RedditThreadComment {
-                body: body.to_owned(),
-                id: id.to_owned(),
-                permalink: permalink.to_owned(),
-            });
+        let mut threads = vec![];
+
+        for entry in entries {
+            let is_comment = entry
+                .file_name()
+                .and_then(|x| x.to_str())
+                .is_some_and(|x| x.starts_with("comment_"));
+
+            if entry.is_dir() || (entry.extension() == Some("json".as_ref()) && !is_comment) {
+                threads.extend(load(&entry)?);
+            }
         }
 
-        Ok(comments)
+        return Ok(threads);
+    }
+
+    let context = || format!("Couldn't parse {}", path.display());
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("Couldn't read {}", path.display()))?;
+    let value = serde_json::from_str::<serde_json::Value>(&data).with_context(context)?;
+
+    // Thread page is an array of thread listing and comments listing.
+    if value.is_array() {
+        let mut listings = serde_json::from_value::<Vec<Thing>>(value)
+            .with_context(context)?
+            .into_iter();
+
+        let link = match listings.next() {
+            Some(Thing::Listing(listing)) => listing.children.into_iter().find_map(|x| match x {
+                Thing::Link(link) => Some(link),
+                _ => None,
+            }),
+            _ => None,
+        }
+        .with_context(|| format!("No thread found in {}", path.display()))?;
+
+        let mut thread = RedditThread::new(link);
+
+        if let (Some(thread), Some(Thing::Listing(listing))) = (&mut thread, listings.next()) {
+            let mut things = vec![];
+            flatten_comments(listing.children, &mut things, &mut vec![]);
+            thread.things = Some(things);
+        }
+
+        return Ok(thread.into_iter().collect());
+    }
+
+    if value.get("kind").is_some() {
+        return match serde_json::from_value::<Thing>(value).with_context(context)? {
+            Thing::Listing(listing) => Ok(listing
+                .children
+                .into_iter()
+                .filter_map(|x| match x {
+                    Thing::Link(link) => RedditThread::new(link),
+                    _ => None,
+                })
+                .collect()),
+            Thing::Link(link) => Ok(RedditThread::new(link).into_iter().collect()),
+            _ => bail!("No threads found in {}", path.display()),
+        };
     }
+
+    let mut thread = serde_json::from_value::<RedditThread>(value).with_context(context)?;
+    thread.things = Some(load_saved_comments(
+        path.parent().unwrap_or_else(|| Path::new(".")),
+        &thread.id,
+    )?);
+    Ok(vec![thread])
 }
 
-pub fn fetch(client: &reqwest::blocking::Client, url: &str, limit: u16) -> Result<Vec<RedditThread>> {
-    let re = regex::Regex::new(r"r/\w*").unwrap();
-    let subreddit_prefixed = re
-        .captures(url)
-        .context("No subreddit is provided.")?
-        .get(0)
-        .unwrap()
-        .as_str();
+/// Read comment_N.json files saved by redtake.
+/// Parent of each comment is recovered from depth of comments narrated before it.
+fn load_saved_comments(dir: &Path, thread_id: &str) -> Result<Vec<Comment>> {
+    let mut files = vec![];
 
-    // Check whether the given url is thread of that subreddit.
-    // (?<=comments\/)\w*
-    // error: look-around, including look-ahead and look-behind, is not supported
-    let re = regex::Regex::new(r"comments/\w*").unwrap();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
 
-    if let Some(thread_id) = re.captures(url) {
-        let thread_url = format!(
-            "https://reddit.com/{}/{}/.json?limit=0&depth=0",
-            subreddit_prefixed,
-            thread_id.get(0).unwrap().as_str()
-        );
+        if let Some(n) = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .and_then(|x| x.strip_prefix("comment_"))
+            .and_then(|x| x.strip_suffix(".json"))
+            .and_then(|x| x.parse::<usize>().ok())
+        {
+            files.push((n, path));
+        }
+    }
 
-        let res = client
-            .get(&thread_url)
-            .send()?
-            .json::<serde_json::Value>()?;
-
-        if let Some(thread) = RedditThread::new(
-            &res.as_array().unwrap()[0]["data"]["children"]
-                .as_array()
-                .unwrap()[0],
-        ) {
-            return Ok(vec![thread]);
-        }
-    } else {
-        let hot_url = format!(
-            "https://reddit.com/{}/hot.json?limit={}",
-            subreddit_prefixed,
+    files.sort();
+
+    let mut things = vec![];
+    let mut parents: Vec<String> = vec![];
+
+    for (_, path) in files {
+        let comment =
+            serde_json::from_reader::<_, RedditThreadComment>(std::fs::File::open(&path)?)
+                .with_context(|| format!("Couldn't parse {}", path.display()))?;
+
+        parents.truncate(comment.depth as usize);
+        let parent_id = match parents.last() {
+            Some(parent) => format!("t1_{}", parent),
+            None => format!("t3_{}", thread_id),
+        };
+        parents.push(comment.id.clone());
+
+        things.push(Comment {
+            author: comment.author,
+            author_flair_text: comment.author_flair_text,
+            body: comment.body,
+            created_utc: comment.created_utc,
+            distinguished: comment.distinguished,
+            edited: comment.edited,
+            gilded: comment.gilded,
+            id: comment.id,
+            parent_id,
+            permalink: comment.permalink,
+            replies: vec![],
+            score: comment.score,
+            stickied: false,
+            total_awards_received: comment.total_awards_received,
+        });
+    }
+
+    Ok(things)
+}
+
+/// Flatten comment listing (including nested replies) into comments
+/// and collect ids of unexpanded "more" stubs.
+fn flatten_comments(children: Vec<Thing>, things: &mut Vec<Comment>, more: &mut Vec<String>) {
+    for child in children {
+        match child {
+            Thing::Comment(mut comment) => {
+                let replies = std::mem::take(&mut comment.replies);
+                things.push(comment);
+                flatten_comments(replies, things, more);
+            }
+            // "continue this thread" stubs have no children.
+            Thing::More(stub) => more.extend(stub.children),
+            _ => (),
+        }
+    }
+}
+
+fn comment_tree(
+    things: &[Comment],
+    parent_id: &str,
+    depth: u8,
+    max_depth: u8,
+    max_length: usize,
+) -> Vec<RedditThreadComment> {
+    let mut comments = vec![];
+
+    for thing in things.iter().filter(|x| x.parent_id == parent_id) {
+        if thing.stickied
+            || thing.body.contains("[removed]")
+            || thing.body.contains("[deleted]")
+            || thing.body.len() > max_length
+        {
+            continue;
+        }
+
+        let replies = if depth < max_depth {
+            comment_tree(
+                things,
+                &format!("t1_{}", thing.id),
+                depth + 1,
+                max_depth,
+                max_length,
+            )
+        } else {
+            vec![]
+        };
+
+        comments.push(RedditThreadComment {
+            author: thing.author.clone(),
+            author_flair_text: thing.author_flair_text.clone(),
+            body: thing.body.clone(),
+            created_utc: thing.created_utc,
+            depth,
+            distinguished: thing.distinguished.clone(),
+            edited: thing.edited,
+            gilded: thing.gilded,
+            id: thing.id.clone(),
+            permalink: thing.permalink.clone(),
+            score: thing.score,
+            total_awards_received: thing.total_awards_received,
+            replies,
+        });
+    }
+
+    comments
+}
+
+pub fn fetch(
+    client: &Client,
+    input: &Input,
+    limit: u16,
+    sort: &Sort,
+    search_sort: &SearchSort,
+    time: Option<&Time>,
+) -> Result<Vec<RedditThread>> {
+    // Reddit uses its own default time window when it isn't given.
+    let time_param = time.map(|x| ("t", x.to_possible_value().unwrap().get_name()));
+
+    match input {
+        Input::Subreddit(subreddit) => fetch_listing(
+            client,
+            &format!(
+                "/r/{}/{}.json",
+                subreddit,
+                sort.to_possible_value().unwrap().get_name()
+            ),
+            &time_param.into_iter().collect::<Vec<_>>(),
             limit,
-        );
+        ),
+        Input::Multireddit { user, name } => fetch_listing(
+            client,
+            &format!(
+                "/user/{}/m/{}/{}.json",
+                user,
+                name,
+                sort.to_possible_value().unwrap().get_name()
+            ),
+            &time_param.into_iter().collect::<Vec<_>>(),
+            limit,
+        ),
+        Input::User(user) => {
+            if let Sort::Rising = sort {
+                bail!("Rising sort order is not supported for user submissions.");
+            }
 
-        let res = client.get(&hot_url).send()?.json::<serde_json::Value>()?;
-        let mut hot_threads = vec![];
+            // Each submission keeps its own subreddit, so threads may belong to many subreddits.
+            fetch_listing(
+                client,
+                &format!("/user/{}/submitted.json", user),
+                &[("sort", sort.to_possible_value().unwrap().get_name())]
+                    .into_iter()
+                    .chain(time_param)
+                    .collect::<Vec<_>>(),
+                limit,
+            )
+        }
+        Input::File(path) => load(path),
+        Input::Thread { id, .. } => Ok(fetch_thread(client, id)?.into_iter().collect()),
+        Input::Comment { thread_id, id, .. } => {
+            let mut thread = fetch_thread(client, thread_id)?;
+
+            if let Some(thread) = &mut thread {
+                thread.comment = Some(id.to_owned());
+            }
+
+            Ok(thread.into_iter().collect())
+        }
+        Input::Share(url) => {
+            // Share links redirect to the actual thread url.
+            let res = client.send(client.get(url))?;
 
-        for child in res["data"]["children"].as_array().unwrap() {
-            if let Some(thread) = RedditThread::new(&child) {
-                hot_threads.push(thread);
+            match Input::parse(res.url().as_str())? {
+                Input::Share(_) => bail!("Couldn't resolve share link {}", url),
+                input => fetch(client, &input, limit, sort, search_sort, time),
+            }
+        }
+        Input::Search { subreddit, query } => {
+            if query.is_empty() {
+                bail!("No search query is provided.");
             }
+
+            let mut params = vec![
+                ("q", query.as_str()),
+                ("sort", search_sort.to_possible_value().unwrap().get_name()),
+                ("type", "link"),
+            ];
+            params.extend(time_param);
+
+            let path = match subreddit {
+                Some(subreddit) => {
+                    params.push(("restrict_sr", "1"));
+                    format!("/r/{}/search.json", subreddit)
+                }
+                None => "/search.json".to_owned(),
+            };
+
+            fetch_listing(client, &path, &params, limit)
         }
+    }
+}
 
-        return Ok(hot_threads);
+fn fetch_thread(client: &Client, id: &str) -> Result<Option<RedditThread>> {
+    let res = client
+        .json::<Vec<Thing>>(
+            client
+                .reddit(&format!("/comments/{}/.json", id))?
+                .query(&[("limit", "0"), ("depth", "0")]),
+        )
+        .with_context(|| format!("Couldn't parse thread {}", id))?;
+
+    if let Some(Thing::Listing(listing)) = res.into_iter().next() {
+        if let Some(Thing::Link(link)) = listing.children.into_iter().next() {
+            return Ok(RedditThread::new(link));
+        }
     }
 
-    Ok(vec![])
+    Ok(None)
+}
+
+/// Fetch threads from listing at reddit api `path`.
+fn fetch_listing(
+    client: &Client,
+    path: &str,
+    params: &[(&str, &str)],
+    limit: u16,
+) -> Result<Vec<RedditThread>> {
+    let mut threads = vec![];
+    let mut seen = std::collections::HashSet::new();
+    let mut after: Option<String> = None;
+
+    // Reddit caps listing pages at 100 threads, so bigger limits are
+    // walked page by page using the after cursor.
+    while threads.len() < limit as usize {
+        let mut request = client
+            .reddit(path)?
+            .query(params)
+            .query(&[("limit", (limit as usize - threads.len()).min(100))]);
+
+        if let Some(after) = &after {
+            request = request.query(&[("after", after)]);
+        }
+
+        let listing = match client.json::<Thing>(request)? {
+            Thing::Listing(listing) => listing,
+            _ => bail!("Expected a listing of threads from {}", path),
+        };
+        let is_empty = listing.children.is_empty();
+
+        for child in listing.children {
+            if let Thing::Link(link) = child {
+                if let Some(thread) = RedditThread::new(link) {
+                    if seen.insert(thread.id.clone()) {
+                        threads.push(thread);
+                    }
+                }
+            }
+        }
+
+        match listing.after {
+            Some(cursor) if !is_empty => after = Some(cursor),
+            _ => break,
+        }
+    }
+
+    threads.truncate(limit as usize);
+    Ok(threads)
 }
 
 // let res = client
@@ -174,3 +1200,138 @@ pub fn fetch(client: &reqwest::blocking::Client, url: &str, limit: u16) -> Resul
 //     res["data"]["title"].as_str().unwrap(),
 //     res["data"]["public_description"].as_str().unwrap()
 // );
+
+#[cfg(test)]
+mod tests {
+    use super::{load, Blocklist, Comment, RedditThread};
+    use crate::client::tests::serve;
+    use crate::client::Client;
+
+    #[test]
+    fn blocklist() {
+        let blocklist = Blocklist::new(
+            &["dick".to_owned(), " ".to_owned(), "c++".to_owned()],
+            &[r"\d{3}-\d{4}".to_owned()],
+        )
+        .unwrap();
+
+        assert!(blocklist.is_match("What a DICK move"));
+        assert!(!blocklist.is_match("Read some Dickens"));
+        assert!(blocklist.is_match("I write c++ at work"));
+        assert!(blocklist.is_match("call 555-1234"));
+        assert!(!blocklist.is_match("nothing here"));
+
+        assert!(!Blocklist::new(&[], &[]).unwrap().is_match("anything"));
+        assert!(Blocklist::new(&[], &["(".to_owned()])
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid blocklist pattern"));
+    }
+
+    #[test]
+    fn starting_comment_is_not_dropped_silently() {
+        let mut thread = serde_json::from_value::<RedditThread>(serde_json::json!({
+            "id": "vus3ch",
+            "over_18": false,
+            "permalink": "/r/AskReddit/comments/vus3ch/x/",
+            "selftext": "",
+            "title": "What hits different at 2am?",
+        }))
+        .unwrap();
+        let comment = |id: &str, parent_id: &str, body: &str| {
+            serde_json::from_value::<Comment>(serde_json::json!({
+                "body": body,
+                "id": id,
+                "parent_id": parent_id,
+                "permalink": format!("/r/AskReddit/comments/vus3ch/x/{}/", id),
+            }))
+            .unwrap()
+        };
+        thread.things = Some(vec![
+            comment("long", "t3_vus3ch", &"a".repeat(600)),
+            comment("removed", "t3_vus3ch", "[removed]"),
+            comment("short", "t3_vus3ch", "Silence."),
+            comment("reply", "t1_long", &"b".repeat(1200)),
+        ]);
+        let client = Client::new("test").unwrap();
+
+        thread.comment = Some("long".to_owned());
+        let error = thread.comments(&client, 500, 10, false).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Comment long of thread vus3ch is longer"));
+
+        thread.comment = Some("removed".to_owned());
+        let error = thread.comments(&client, 500, 10, false).unwrap_err();
+        assert!(error.to_string().contains("removed or deleted"));
+
+        // Replies are still filtered as usual.
+        thread.comment = Some("long".to_owned());
+        let comments = thread.comments(&client, 1000, 10, false).unwrap();
+        assert_eq!(comments.len(), 1);
+        assert!(comments[0].replies.is_empty());
+
+        thread.comment = Some("short".to_owned());
+        let comments = thread.comments(&client, 500, 10, false).unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].body, "Silence.");
+    }
+
+    #[test]
+    fn save_media_skips_failed_downloads() {
+        let (url, server) = serve(vec![(403, vec![]), (200, b"png".to_vec())]);
+        let thread = serde_json::from_value::<RedditThread>(serde_json::json!({
+            "id": "vus3ch",
+            "over_18": false,
+            "permalink": "/r/pics/comments/vus3ch/x/",
+            "selftext": "",
+            "title": "Gallery",
+            "media": [format!("{}/removed.jpg", url), format!("{}/cat.png?width=640", url)],
+        }))
+        .unwrap();
+        let dir = std::env::temp_dir().join(format!("redtake-media-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("images")).unwrap();
+
+        let media = thread.save_media(&Client::new("test").unwrap(), dir.to_str().unwrap());
+        let image = std::fs::read(dir.join("images").join("media_2.png"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(media.len(), 2);
+        assert!(media[0]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("removed.jpg"));
+        assert_eq!(media[1].as_ref().unwrap(), "images/media_2.png");
+        assert_eq!(image.unwrap(), b"png");
+    }
+
+    #[test]
+    fn load_dumps_without_metadata() {
+        let dir = std::env::temp_dir().join(format!("redtake-load-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("thread.json"),
+            r#"{"id": "vus3ch", "over_18": false, "permalink": "/r/AskReddit/comments/vus3ch/x/",
+                "selftext": "", "title": "What hits different at 2am?"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("comment_0.json"),
+            r#"{"body": "Silence.", "id": "ifb2x6e", "permalink": "/r/AskReddit/comments/vus3ch/x/ifb2x6e/"}"#,
+        )
+        .unwrap();
+
+        let threads = load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let threads = threads.unwrap();
+
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].title, "What hits different at 2am?");
+        let things = threads[0].things.as_ref().unwrap();
+        assert_eq!(things.len(), 1);
+        assert_eq!(things[0].body, "Silence.");
+        assert_eq!(things[0].parent_id, "t3_vus3ch");
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::Result;
+
+use super::{TtsProvider, Voice};
+use crate::client::Client;
+
+/// Default url of translate_tts endpoint where `{tld}` is replaced by top level domain of voice.
+pub const GOOGLE_URL: &str = "https://translate.google.{tld}/translate_tts";
+
+/// Voice name, language code, top level domain (which decides accent) and language of voices.
+const VOICES: &[(&str, &str, &str, &str)] = &[
+    ("de-de-google-translate", "de", "de", "de-DE"),
+    ("en-au-google-translate", "en", "com.au", "en-AU"),
+    ("en-ca-google-translate", "en", "ca", "en-CA"),
+    ("en-ie-google-translate", "en", "ie", "en-IE"),
+    ("en-in-google-translate", "en", "co.in", "en-IN"),
+    ("en-uk-google-translate", "en", "co.uk", "en-GB"),
+    ("en-us-google-translate", "en", "com", "en-US"),
+    ("en-za-google-translate", "en", "co.za", "en-ZA"),
+    ("es-es-google-translate", "es", "es", "es-ES"),
+    ("es-mx-google-translate", "es", "com.mx", "es-MX"),
+    ("fr-ca-google-translate", "fr", "ca", "fr-CA"),
+    ("fr-fr-google-translate", "fr", "fr", "fr-FR"),
+    ("hi-in-google-translate", "hi", "co.in", "hi-IN"),
+    ("id-id-google-translate", "id", "co.id", "id-ID"),
+    ("it-it-google-translate", "it", "it", "it-IT"),
+    ("jp-jp-google-translate", "ja", "co.jp", "ja-JP"),
+    ("kr-kr-google-translate", "ko", "co.kr", "ko-KR"),
+    ("nl-nl-google-translate", "nl", "nl", "nl-NL"),
+    ("pt-br-google-translate", "pt", "com.br", "pt-BR"),
+    ("pt-pt-google-translate", "pt", "pt", "pt-PT"),
+    ("ru-ru-google-translate", "ru", "ru", "ru-RU"),
+];
+
+/// Google Translate text to speech.
+pub struct Google {
+    url: String,
+}
+
+impl Google {
+    /// `url` of translate_tts endpoint, see [`GOOGLE_URL`].
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+        }
+    }
+}
+
+impl TtsProvider for Google {
+    fn voices(&self) -> Vec<Voice> {
+        VOICES
+            .iter()
+            .map(|(name, id, _, language)| Voice::new(name, id, language))
+            .collect()
+    }
+
+    fn max_chunk_length(&self) -> usize {
+        200
+    }
+
+    fn synthesize(&self, client: &Client, voice: &Voice, text: &str) -> Result<Vec<u8>> {
+        let tld = VOICES
+            .iter()
+            .find(|x| x.0 == voice.name)
+            .map(|x| x.2)
+            .unwrap_or("com");
+
+        client.bytes(client.get(self.url.replace("{tld}", tld)).query(&[
+            ("ie", "UTF-8"),
+            ("client", "tw-ob"),
+            ("tl", voice.id.as_str()),
+            ("q", text),
+            ("total", "1"),
+            ("idx", "0"),
+            ("textlen", &text.chars().count().to_string()),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::tests::serve;
+    use crate::client::Client;
+    use crate::config::Tts;
+
+    #[test]
+    fn synthesize_with_stand_in_server() {
+        let (url, server) = serve(vec![(200, b"mp3".to_vec())]);
+        let tts = Tts {
+            google_url: format!("{}/{{tld}}/translate_tts", url),
+            ..Default::default()
+        };
+        let registry = tts.registry(false).unwrap();
+        let (provider, voice) = registry.find("en-uk-google-translate").unwrap();
+
+        let audio = provider
+            .synthesize(&Client::new("test").unwrap(), &voice, "Héllo & bye")
+            .unwrap();
+        assert_eq!(audio, b"mp3");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with(
+            "GET /co.uk/translate_tts?ie=UTF-8&client=tw-ob&tl=en&q=H%C3%A9llo+%26+bye&total=1&idx=0&textlen=11 "
+        ));
+    }
+
+    #[test]
+    fn registered_once() {
+        let registry = Tts::default().registry(false).unwrap();
+        let providers = registry
+            .providers
+            .iter()
+            .filter(|x| x.voices().iter().any(|x| x.name.contains("-google-")))
+            .count();
+        assert_eq!(providers, 1);
+    }
+}
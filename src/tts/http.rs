@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use reqwest::Method;
+use serde::Deserialize;
+
+use super::local::{to_mp3, TempFile};
+use super::{TtsProvider, Voice};
+use crate::client::Client;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpResponse {
+    /// Response body is audio.
+    #[default]
+    Raw,
+    /// Response body is json with base64 encoded audio at field path.
+    Base64,
+}
+
+/// Speech synthesis using a http endpoint defined in `[[tts.http]]` section of config file,
+/// like an OpenAI compatible /v1/audio/speech or a self-hosted tts server.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Http {
+    /// Voice names mapped to voice ids used in requests.
+    pub voices: BTreeMap<String, String>,
+    /// Language (like en-US) of voices.
+    pub language: String,
+    /// Request url, where `{voice}` and `{text}` are replaced by percent encoded voice id and text.
+    pub url: String,
+    pub method: String,
+    pub headers: BTreeMap<String, String>,
+    /// Request body, where `{voice}` and `{text}` are replaced by voice id and text escaped for json strings.
+    pub body: Option<String>,
+    pub response: HttpResponse,
+    /// Path of base64 encoded audio in json response like data.0.audio,
+    /// whole response is decoded when empty.
+    pub field: String,
+    /// Audio format of response, audio other than mp3 is converted using ffmpeg.
+    pub format: String,
+    pub max_chars: usize,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self {
+            voices: BTreeMap::new(),
+            language: String::new(),
+            url: String::new(),
+            method: "POST".to_owned(),
+            headers: BTreeMap::new(),
+            body: None,
+            response: HttpResponse::Raw,
+            field: String::new(),
+            format: "mp3".to_owned(),
+            max_chars: 1000,
+        }
+    }
+}
+
+impl TtsProvider for Http {
+    fn voices(&self) -> Vec<Voice> {
+        self.voices
+            .iter()
+            .map(|(name, id)| Voice::new(name, id, &self.language))
+            .collect()
+    }
+
+    fn max_chunk_length(&self) -> usize {
+        self.max_chars.max(1)
+    }
+
+    fn synthesize(&self, client: &Client, voice: &Voice, text: &str) -> Result<Vec<u8>> {
+        let method = Method::from_bytes(self.method.to_uppercase().as_bytes())
+            .with_context(|| format!("Invalid http tts method {}", self.method))?;
+        let url = self
+            .url
+            .replace("{voice}", &encode_query(&voice.id))
+            .replace("{text}", &encode_query(text));
+        let mut req = client.request(method, &url);
+
+        for (name, value) in &self.headers {
+            req = req.header(name, value);
+        }
+
+        if let Some(body) = &self.body {
+            req = req.body(
+                body.replace("{voice}", &escape_json(&voice.id))
+                    .replace("{text}", &escape_json(text)),
+            );
+        }
+
+        let res = client
+            .bytes(req)
+            .with_context(|| format!("Couldn't perform tts using {}", url))?;
+
+        let audio = match self.response {
+            HttpResponse::Raw => res,
+            HttpResponse::Base64 => {
+                let encoded = if self.field.is_empty() {
+                    String::from_utf8_lossy(&res).trim().to_owned()
+                } else {
+                    let value = serde_json::from_slice::<serde_json::Value>(&res)
+                        .context("Couldn't parse http tts response as json")?;
+                    let pointer = format!("/{}", self.field.replace('.', "/"));
+
+                    match value.pointer(&pointer).and_then(|x| x.as_str()) {
+                        Some(encoded) => encoded.to_owned(),
+                        None => bail!("Http tts response doesn't have {} field.", self.field),
+                    }
+                };
+
+                base64::decode(encoded).context("Couldn't decode base64 audio of http tts")?
+            }
+        };
+
+        if self.format.eq_ignore_ascii_case("mp3") {
+            return Ok(audio);
+        }
+
+        let file = TempFile::new(&self.format);
+        std::fs::write(&file.0, audio)?;
+        to_mp3(&file.0)
+    }
+}
+
+/// Percent encode value for use in url.
+fn encode_query(value: &str) -> String {
+    value
+        .bytes()
+        .map(|x| match x {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (x as char).to_string()
+            }
+            _ => format!("%{:02X}", x),
+        })
+        .collect()
+}
+
+/// Escape value for use inside a json string.
+fn escape_json(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap();
+    quoted[1..quoted.len() - 1].to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Http, HttpResponse};
+    use crate::client::tests::serve;
+    use crate::client::Client;
+    use crate::tts::TtsProvider;
+
+    fn http(url: String) -> Http {
+        Http {
+            voices: [("en-us-custom-a".to_owned(), "a b/c".to_owned())].into(),
+            url,
+            ..Default::default()
+        }
+    }
+
+    fn synthesize(http: &Http, text: &str) -> anyhow::Result<Vec<u8>> {
+        let voice = http.voices().remove(0);
+        http.synthesize(&Client::new("test").unwrap(), &voice, text)
+    }
+
+    #[test]
+    fn post_body_template() {
+        let (url, server) = serve(vec![(200, b"mp3".to_vec())]);
+        let http = Http {
+            headers: [("X-Api-Key".to_owned(), "secret".to_owned())].into(),
+            body: Some(r#"{"voice": "{voice}", "input": "{text}"}"#.to_owned()),
+            ..http(format!("{}/speech/{{voice}}?q={{text}}", url))
+        };
+
+        let audio = synthesize(&http, "He said \"hi\"\nbye & go").unwrap();
+        assert_eq!(audio, b"mp3");
+
+        let request = &server.join().unwrap()[0];
+        assert!(
+            request.starts_with("POST /speech/a%20b%2Fc?q=He%20said%20%22hi%22%0Abye%20%26%20go ")
+        );
+        assert!(request.contains("x-api-key: secret\r\n"));
+        assert!(request.ends_with(r#"{"voice": "a b/c", "input": "He said \"hi\"\nbye & go"}"#));
+    }
+
+    #[test]
+    fn base64_field() {
+        let (url, server) = serve(vec![(200, br#"{"data": [{"audio": "bXAz"}]}"#.to_vec())]);
+        let http = Http {
+            method: "get".to_owned(),
+            response: HttpResponse::Base64,
+            field: "data.0.audio".to_owned(),
+            ..http(format!("{}/tts?text={{text}}", url))
+        };
+
+        assert_eq!(synthesize(&http, "hello").unwrap(), b"mp3");
+        assert!(server.join().unwrap()[0].starts_with("GET /tts?text=hello "));
+    }
+
+    #[test]
+    fn missing_field() {
+        let (url, server) = serve(vec![(200, br#"{"data": []}"#.to_vec())]);
+        let http = Http {
+            response: HttpResponse::Base64,
+            field: "data.0.audio".to_owned(),
+            ..http(format!("{}/tts", url))
+        };
+
+        let error = synthesize(&http, "hello").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Http tts response doesn't have data.0.audio field."
+        );
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,233 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+
+use super::{TtsProvider, Voice};
+use crate::client::Client;
+
+/// Voice name, espeak-ng voice and language of voices.
+const ESPEAK_VOICES: &[(&str, &str, &str)] = &[
+    ("de-de-espeak-female", "de+f3", "de-DE"),
+    ("de-de-espeak-male", "de", "de-DE"),
+    ("en-uk-espeak-female", "en+f3", "en-GB"),
+    ("en-uk-espeak-male", "en", "en-GB"),
+    ("en-us-espeak-female", "en-us+f3", "en-US"),
+    ("en-us-espeak-male", "en-us", "en-US"),
+    ("es-es-espeak-female", "es+f3", "es-ES"),
+    ("es-es-espeak-male", "es", "es-ES"),
+    ("es-mx-espeak-female", "es-419+f3", "es-MX"),
+    ("es-mx-espeak-male", "es-419", "es-MX"),
+    ("fr-fr-espeak-female", "fr+f3", "fr-FR"),
+    ("fr-fr-espeak-male", "fr", "fr-FR"),
+    ("id-id-espeak-female", "id+f3", "id-ID"),
+    ("id-id-espeak-male", "id", "id-ID"),
+    ("it-it-espeak-female", "it+f3", "it-IT"),
+    ("it-it-espeak-male", "it", "it-IT"),
+    ("jp-jp-espeak-female", "ja+f3", "ja-JP"),
+    ("jp-jp-espeak-male", "ja", "ja-JP"),
+    ("kr-kr-espeak-female", "ko+f3", "ko-KR"),
+    ("kr-kr-espeak-male", "ko", "ko-KR"),
+    ("pt-br-espeak-female", "pt-br+f3", "pt-BR"),
+    ("pt-br-espeak-male", "pt-br", "pt-BR"),
+];
+
+/// Local speech synthesis using espeak-ng.
+pub struct Espeak {
+    command: String,
+}
+
+impl Espeak {
+    /// `command` is name or path of espeak-ng executable.
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_owned(),
+        }
+    }
+}
+
+impl TtsProvider for Espeak {
+    fn voices(&self) -> Vec<Voice> {
+        ESPEAK_VOICES
+            .iter()
+            .map(|(name, id, language)| Voice::new(name, id, language))
+            .collect()
+    }
+
+    fn max_chunk_length(&self) -> usize {
+        10000
+    }
+
+    fn offline(&self) -> bool {
+        true
+    }
+
+    fn synthesize(&self, _: &Client, voice: &Voice, text: &str) -> Result<Vec<u8>> {
+        let wav = TempFile::new("wav");
+        run(
+            Command::new(&self.command)
+                .args(["-v", &voice.id, "-w"])
+                .arg(&wav.0)
+                .arg("--stdin"),
+            text,
+        )?;
+        to_mp3(&wav.0)
+    }
+}
+
+/// Local neural speech synthesis using piper (https://github.com/rhasspy/piper).
+/// Each voice is a piper model.
+pub struct Piper {
+    command: String,
+    models: Vec<PathBuf>,
+}
+
+impl Piper {
+    /// `command` is name or path of piper executable.
+    /// `models` are paths of .onnx models or directories containing them.
+    pub fn new(command: &str, models: &[String]) -> Result<Self> {
+        let mut paths = vec![];
+
+        for model in models {
+            let model = Path::new(model);
+
+            if model.is_dir() {
+                let mut dir = std::fs::read_dir(model)
+                    .with_context(|| {
+                        format!("Couldn't read piper models from {}", model.display())
+                    })?
+                    .filter_map(|x| x.ok().map(|x| x.path()))
+                    .filter(|x| x.extension().is_some_and(|x| x == "onnx"))
+                    .collect::<Vec<_>>();
+                dir.sort();
+                paths.extend(dir);
+            } else if model.exists() {
+                paths.push(model.to_owned());
+            } else {
+                bail!("Piper model {} doesn't exist", model.display());
+            }
+        }
+
+        Ok(Self {
+            command: command.to_owned(),
+            models: paths,
+        })
+    }
+}
+
+impl TtsProvider for Piper {
+    /// Voices are named after models, like en-us-piper-amy-medium for en_US-amy-medium.onnx.
+    fn voices(&self) -> Vec<Voice> {
+        self.models
+            .iter()
+            .map(|model| {
+                let stem = model
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned();
+
+                match stem.split_once('-') {
+                    Some((language, name)) if language.contains('_') => Voice::new(
+                        &format!(
+                            "{}-piper-{}",
+                            language.replace('_', "-").to_lowercase(),
+                            name.to_lowercase()
+                        ),
+                        &model.to_string_lossy(),
+                        &language.replace('_', "-"),
+                    ),
+                    _ => Voice::new(
+                        &format!("piper-{}", stem.to_lowercase()),
+                        &model.to_string_lossy(),
+                        "",
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    fn max_chunk_length(&self) -> usize {
+        10000
+    }
+
+    fn offline(&self) -> bool {
+        true
+    }
+
+    fn synthesize(&self, _: &Client, voice: &Voice, text: &str) -> Result<Vec<u8>> {
+        let wav = TempFile::new("wav");
+        run(
+            Command::new(&self.command)
+                .args(["--model", &voice.id, "--output_file"])
+                .arg(&wav.0),
+            text,
+        )?;
+        to_mp3(&wav.0)
+    }
+}
+
+/// Run command with text as its standard input.
+fn run(command: &mut Command, text: &str) -> Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Couldn't run {}, make sure it is installed", program))?;
+
+    child.stdin.take().unwrap().write_all(text.as_bytes())?;
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Convert audio file to .mp3 audio.
+pub(super) fn to_mp3(audio: &Path) -> Result<Vec<u8>> {
+    let mp3 = TempFile::new("mp3");
+    let status = Command::new("ffmpeg")
+        .args(["-hide_banner", "-y", "-i"])
+        .arg(audio)
+        .arg(&mp3.0)
+        .stderr(Stdio::null())
+        .status()
+        .context("Couldn't run ffmpeg for converting tts audio")?;
+
+    if !status.success() {
+        bail!("ffmpeg failed to convert {} to mp3", audio.display());
+    }
+
+    Ok(std::fs::read(&mp3.0)?)
+}
+
+/// File in temporary directory which is removed when dropped.
+pub(super) struct TempFile(pub(super) PathBuf);
+
+impl TempFile {
+    pub(super) fn new(extension: &str) -> Self {
+        static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+        Self(std::env::temp_dir().join(format!(
+            "redtake-{}-{}.{}",
+            std::process::id(),
+            COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+            extension
+        )))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
@@ -0,0 +1,466 @@
+mod google;
+mod http;
+mod local;
+mod polly;
+mod text;
+mod tiktok;
+
+pub use google::{Google, GOOGLE_URL};
+pub use http::{Http, HttpResponse};
+pub use local::{Espeak, Piper};
+pub use polly::Polly;
+pub(crate) use text::whole_word;
+pub use text::{normalize, Lexicon, Mask, MaskMode};
+pub use tiktok::TikTok;
+
+use std::io::Write;
+
+use anyhow::{bail, Context, Result};
+
+use crate::client::Client;
+
+/// Speech synthesis backend.
+/// Providers are registered in a [`Registry`] which looks up their voices by name.
+pub trait TtsProvider {
+    /// Voices provided by this provider.
+    fn voices(&self) -> Vec<Voice>;
+
+    /// Maximum number of characters synthesized by a single request.
+    /// Longer texts are split into chunks which are synthesized one by one.
+    fn max_chunk_length(&self) -> usize;
+
+    /// Languages (like en-US) of voices.
+    fn languages(&self) -> Vec<String> {
+        let mut languages = self
+            .voices()
+            .into_iter()
+            .map(|x| x.language)
+            .collect::<Vec<_>>();
+        languages.sort();
+        languages.dedup();
+        languages
+    }
+
+    /// Provider synthesizes speech locally without any network request.
+    /// Speech of such providers isn't cached and can be synthesized in offline mode.
+    fn offline(&self) -> bool {
+        false
+    }
+
+    /// Synthesize text (no longer than max chunk length) into .mp3 audio.
+    fn synthesize(&self, client: &Client, voice: &Voice, text: &str) -> Result<Vec<u8>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct Voice {
+    /// Unique name used for selecting voice.
+    /// Names are in format of [language_code - country_code - service_provider - voice_name].
+    pub name: String,
+    /// Identifier of voice used by its provider.
+    pub id: String,
+    /// Language (like en-US) of voice.
+    pub language: String,
+}
+
+impl Voice {
+    pub fn new(name: &str, id: &str, language: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            id: id.to_owned(),
+            language: language.to_owned(),
+        }
+    }
+}
+
+/// Registered tts providers.
+#[derive(Default)]
+pub struct Registry {
+    providers: Vec<Box<dyn TtsProvider>>,
+    lexicon: Lexicon,
+}
+
+impl Registry {
+    /// Registry of tiktok and polly providers.
+    /// `proxy` uses https://warp-co.rs CORS proxy to bypass geolocation restrictions for some voices.
+    pub fn new(proxy: bool) -> Self {
+        let mut registry = Self::default();
+        registry.register(TikTok::new(proxy));
+        registry.register(Polly::new(proxy));
+        registry
+    }
+
+    /// Pronunciation lexicon applied to normalized text before synthesis.
+    pub fn with_lexicon(mut self, lexicon: Lexicon) -> Self {
+        self.lexicon = lexicon;
+        self
+    }
+
+    /// Add a provider, its voices take precedence over voices of same name of earlier providers.
+    pub fn register<P: TtsProvider + 'static>(&mut self, provider: P) {
+        self.providers.insert(0, Box::new(provider));
+    }
+
+    /// Voices of all providers sorted by name.
+    pub fn voices(&self) -> Vec<Voice> {
+        let mut voices = self
+            .providers
+            .iter()
+            .flat_map(|x| x.voices())
+            .collect::<Vec<_>>();
+        voices.sort_by(|a, b| a.name.cmp(&b.name));
+        voices.dedup_by(|a, b| a.name == b.name);
+        voices
+    }
+
+    /// Provider of voice along with the voice.
+    pub fn find(&self, name: &str) -> Result<(&dyn TtsProvider, Voice)> {
+        for provider in &self.providers {
+            if let Some(voice) = provider.voices().into_iter().find(|x| x.name == name) {
+                return Ok((provider.as_ref(), voice));
+            }
+        }
+
+        bail!(
+            "Voice {} is not available, use `redtake voices` to list available voices.",
+            name
+        )
+    }
+
+    /// Synthesize reddit markdown using voice and save it as .mp3 file.
+    /// Text is normalized, then lexicon is applied and at last words are masked.
+    /// Synthesized speech is reused from cache for same voice and text.
+    pub fn save(
+        &self,
+        client: &Client,
+        voice: &str,
+        text: &str,
+        path: &str,
+        mask: &Mask,
+    ) -> Result<()> {
+        let (provider, voice) = self.find(voice)?;
+        let text = &self.lexicon.apply(&normalize(text));
+
+        if let Some(parts) = mask.bleep(text) {
+            return save_bleeped(client, provider, &voice, parts, path);
+        }
+
+        let mp3 = speech(client, provider, &voice, &mask.substitute(text))?;
+        std::fs::File::create(path)?.write_all(&mp3)?;
+        Ok(())
+    }
+}
+
+/// Synthesize parts of text around masked words separately
+/// and join them with a beep tone in place of masked words using ffmpeg.
+fn save_bleeped(
+    client: &Client,
+    provider: &dyn TtsProvider,
+    voice: &Voice,
+    parts: Vec<&str>,
+    path: &str,
+) -> Result<()> {
+    let mut args = vec!["-hide_banner".to_owned(), "-y".to_owned()];
+    let mut part_paths = vec![];
+    let mut inputs = 0;
+
+    for (i, part) in parts.into_iter().enumerate() {
+        if i != 0 {
+            args.extend([
+                "-f".to_owned(),
+                "lavfi".to_owned(),
+                "-i".to_owned(),
+                format!(
+                    "sine=frequency=1000:sample_rate=24000:duration={}",
+                    BLEEP_DURATION
+                ),
+            ]);
+            inputs += 1;
+        }
+
+        // Punctuation alone can't be synthesized.
+        if part.chars().any(|x| x.is_alphanumeric()) {
+            let part_path = format!("{}.part{}.mp3", path, i);
+            std::fs::write(&part_path, speech(client, provider, voice, part)?)?;
+            args.extend(["-i".to_owned(), part_path.clone()]);
+            part_paths.push(part_path);
+            inputs += 1;
+        }
+    }
+
+    args.extend([
+        "-filter_complex".to_owned(),
+        format!(
+            "{}concat=n={}:v=0:a=1[audio]",
+            (0..inputs)
+                .map(|x| format!("[{}:a]", x))
+                .collect::<String>(),
+            inputs
+        ),
+        "-map".to_owned(),
+        "[audio]".to_owned(),
+        path.to_owned(),
+    ]);
+
+    let status = std::process::Command::new("ffmpeg")
+        .args(&args)
+        .stderr(std::process::Stdio::null())
+        .status()
+        .context("Couldn't run ffmpeg for bleeping tts audio")?;
+
+    for part_path in part_paths {
+        std::fs::remove_file(part_path)?;
+    }
+
+    if !status.success() {
+        bail!("ffmpeg failed to bleep tts audio for {}", path);
+    }
+
+    Ok(())
+}
+
+/// Synthesize text in chunks of provider's max chunk length.
+fn speech(
+    client: &Client,
+    provider: &dyn TtsProvider,
+    voice: &Voice,
+    text: &str,
+) -> Result<Vec<u8>> {
+    let synthesize = || {
+        let mut audio = vec![];
+
+        for chunk in text_chunks(text, provider.max_chunk_length()) {
+            audio.extend(provider.synthesize(client, voice, &chunk)?);
+        }
+
+        Ok(audio)
+    };
+
+    if provider.offline() {
+        return synthesize();
+    }
+
+    client.cached(
+        "tts",
+        &format!("{}\n{}", voice.name, text),
+        None,
+        synthesize,
+    )
+}
+
+/// Rough estimate of speech duration (in seconds) of text,
+/// based on an average speaking rate of 15 characters per second.
+pub fn estimate_duration(text: &str) -> f32 {
+    text.chars().count() as f32 / 15.0
+}
+
+/// Duration (in seconds) of beep tone used in place of masked words.
+const BLEEP_DURATION: f32 = 0.4;
+
+/// Split text into chunks of at most `size` characters.
+/// Chunks are split at the last sentence, clause or word boundary (in that order of preference)
+/// within the limit and words longer than the limit are split between characters.
+fn text_chunks(text: &str, size: usize) -> Vec<String> {
+    let size = size.max(1);
+    let mut chunks = vec![];
+    let mut rest = text.trim();
+
+    while !rest.is_empty() {
+        let end = match rest.char_indices().nth(size) {
+            Some((end, _)) => end,
+            None => {
+                chunks.push(rest.to_owned());
+                break;
+            }
+        };
+
+        let (mut sentence, mut clause, mut word) = (None, None, None);
+
+        for (i, c) in rest[..end].char_indices() {
+            let after = i + c.len_utf8();
+            let spaced = rest[after..].starts_with(char::is_whitespace);
+
+            if matches!(c, '。' | '！' | '？') || (matches!(c, '.' | '!' | '?' | '…') && spaced)
+            {
+                sentence = Some(after);
+            } else if matches!(c, '，' | '、' | '；' | '：')
+                || (matches!(c, ',' | ';' | ':' | ')' | '—') && spaced)
+            {
+                clause = Some(after);
+            } else if c.is_whitespace() {
+                word = Some(i);
+            }
+        }
+
+        let split = sentence.or(clause).or(word).unwrap_or(end);
+        let chunk = rest[..split].trim_end();
+
+        if !chunk.is_empty() {
+            chunks.push(chunk.to_owned());
+        }
+
+        rest = rest[split..].trim_start();
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{text_chunks, Lexicon, Mask, MaskMode, Registry, TtsProvider, Voice};
+    use crate::client::Client;
+
+    /// Provider which "synthesizes" text as is.
+    struct Echo;
+
+    impl TtsProvider for Echo {
+        fn voices(&self) -> Vec<Voice> {
+            vec![Voice::new("echo", "echo", "en-US")]
+        }
+
+        fn max_chunk_length(&self) -> usize {
+            1000
+        }
+
+        fn offline(&self) -> bool {
+            true
+        }
+
+        fn synthesize(&self, _: &Client, _: &Voice, text: &str) -> anyhow::Result<Vec<u8>> {
+            Ok(text.as_bytes().to_vec())
+        }
+    }
+
+    #[test]
+    fn save_applies_lexicon_after_normalize() {
+        let mut registry = Registry::default().with_lexicon(Lexicon::load_toml(None).unwrap());
+        registry.register(Echo);
+        let mask = Mask::new(MaskMode::Substitute, &[], "beep").unwrap();
+        let path = std::env::temp_dir().join(format!("redtake-save-{}.mp3", std::process::id()));
+
+        registry
+            .save(
+                &Client::new("test").unwrap(),
+                "echo",
+                "[my post](https://www.reddit.com/r/AITA/comments/abc/x/) AITA? **TIFU**",
+                path.to_str().unwrap(),
+                &mask,
+            )
+            .unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Words introduced by lexicon are masked too.
+        assert_eq!(text, "my post am I the beep? today I beep up.");
+    }
+
+    /// Characters of generated texts, including multi-byte and sentence/clause punctuation.
+    const ALPHABET: &[char] = &[
+        'a', 'b', 'c', 'é', 'ß', ' ', ' ', ' ', '\n', '\t', '.', ',', '!', '?', ';', ':', ')', '—',
+        '…', '。', '，', '、', '！', '日', '本', '語', '한', '국', '😀', '👍', '🏽', '\u{200D}',
+        '\u{301}',
+    ];
+
+    /// Texts generated from a fixed seed (xorshift), so failures are reproducible.
+    fn texts(count: usize) -> Vec<String> {
+        let mut seed = 0x2545_F491_4F6C_DD1D_u64;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+
+        (0..count)
+            .map(|_| {
+                let length = (next() % 80) as usize;
+                (0..length)
+                    .map(|_| match next() {
+                        x if x % 8 == 0 => {
+                            char::from_u32((x >> 32) as u32 % 0x11_0000).unwrap_or('x')
+                        }
+                        x => ALPHABET[(x >> 8) as usize % ALPHABET.len()],
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chunks_are_within_limit() {
+        for text in texts(1500) {
+            for size in 1..=40 {
+                for chunk in text_chunks(&text, size) {
+                    assert!(!chunk.is_empty(), "{:?} {}", text, size);
+                    assert!(chunk.chars().count() <= size, "{:?} {}", text, size);
+                    assert_eq!(chunk.trim(), chunk, "{:?} {}", text, size);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_preserve_text() {
+        for text in texts(1500) {
+            for size in 1..=40 {
+                let mut rest = text.trim();
+
+                for chunk in text_chunks(&text, size) {
+                    assert!(rest.starts_with(&chunk), "{:?} {}", text, size);
+                    rest = rest[chunk.len()..].trim_start();
+                }
+
+                assert!(rest.is_empty(), "{:?} {}", text, size);
+            }
+        }
+    }
+
+    #[test]
+    fn words_are_split_only_without_space() {
+        for text in texts(1500) {
+            for size in 1..=40 {
+                let mut rest = text.trim();
+
+                for chunk in text_chunks(&text, size) {
+                    rest = &rest[chunk.len()..];
+
+                    // Chunk ends inside a word, which is only allowed when it has no space to split at.
+                    if rest.starts_with(|x: char| !x.is_whitespace())
+                        && !chunk.ends_with(['。', '，', '、', '！'])
+                    {
+                        assert!(
+                            !chunk.contains(char::is_whitespace),
+                            "{:?} {} {:?}",
+                            text,
+                            size,
+                            chunk
+                        );
+                    }
+
+                    rest = rest.trim_start();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_prefer_boundaries() {
+        assert_eq!(
+            text_chunks("Hello there. This is a test, with clauses; and more", 30),
+            ["Hello there.", "This is a test, with clauses;", "and more"]
+        );
+        assert_eq!(
+            text_chunks("今日は良い天気です。明日も晴れるでしょう。", 12),
+            ["今日は良い天気です。", "明日も晴れるでしょう。"]
+        );
+        assert_eq!(
+            text_chunks("Version 1.5 is out. Great", 15),
+            ["Version 1.5 is", "out. Great"]
+        );
+        assert_eq!(
+            text_chunks("abcdefghij word", 4),
+            ["abcd", "efgh", "ij", "word"]
+        );
+        assert_eq!(text_chunks("😀😀😀", 2), ["😀😀", "😀"]);
+        assert!(text_chunks("  \n ", 10).is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::{bail, Result};
+
+use super::{TtsProvider, Voice};
+use crate::client::Client;
+
+/// Voice name, voice id and language of voices.
+const VOICES: &[(&str, &str, &str)] = &[
+    ("en-au-polly-nicole", "Nicole", "en-AU"),
+    ("en-au-polly-russell", "Russell", "en-AU"),
+    ("en-in-polly-raveena", "Raveena", "en-IN"),
+    ("en-uk-polly-amy", "Amy", "en-GB"),
+    ("en-uk-polly-brian", "Brian", "en-GB"),
+    ("en-uk-polly-emma", "Emma", "en-GB"),
+    ("en-us-polly-ivy", "Ivy", "en-US"),
+    ("en-us-polly-joanna", "Joanna", "en-US"),
+    ("en-us-polly-joey", "Joey", "en-US"),
+    ("en-us-polly-justin", "Justin", "en-US"),
+    ("en-us-polly-kendra", "Kendra", "en-US"),
+    ("en-us-polly-kimberly", "Kimberly", "en-US"),
+    ("en-us-polly-matthew", "Matthew", "en-US"),
+    ("en-us-polly-salli", "Salli", "en-US"),
+    ("en-welsh-polly-geraint", "Geraint", "en-GB-WLS"),
+];
+
+/// Amazon Polly voices through streamlabs.
+pub struct Polly {
+    proxy: bool,
+}
+
+impl Polly {
+    pub fn new(proxy: bool) -> Self {
+        Self { proxy }
+    }
+}
+
+impl TtsProvider for Polly {
+    fn voices(&self) -> Vec<Voice> {
+        VOICES
+            .iter()
+            .map(|(name, id, language)| Voice::new(name, id, language))
+            .collect()
+    }
+
+    fn max_chunk_length(&self) -> usize {
+        499
+    }
+
+    fn synthesize(&self, client: &Client, voice: &Voice, text: &str) -> Result<Vec<u8>> {
+        let url = if self.proxy {
+            "https://warp-co.rs/https://streamlabs.com/polly/speak"
+        } else {
+            "https://streamlabs.com/polly/speak"
+        };
+
+        let mut res = client.post(url).query(&[
+            ("voice", voice.id.as_str()),
+            ("text", text),
+            ("service", "polly"),
+        ]);
+
+        if self.proxy {
+            res = res.header("Origin", "https://streamlabs.com/polly/speak")
+        }
+
+        let res = client.json::<serde_json::Value>(res)?;
+
+        if !res["error"].is_null() {
+            bail!(res["error"].as_str().unwrap_or_default().to_owned())
+        }
+
+        match (res["success"].as_bool(), res["speak_url"].as_str()) {
+            (Some(true), Some(speak_url)) => client.bytes(client.get(speak_url)),
+            _ => bail!("Couldn't perform tts."),
+        }
+    }
+}
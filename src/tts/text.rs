@@ -0,0 +1,563 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Words masked when no profanity list is configured.
+const PROFANITY: &[&str] = &[
+    "arse",
+    "asshole",
+    "bastard",
+    "bitch",
+    "bollocks",
+    "bullshit",
+    "cock",
+    "cunt",
+    "dick",
+    "fuck",
+    "motherfucker",
+    "piss",
+    "pussy",
+    "shit",
+    "slut",
+    "twat",
+    "wanker",
+    "whore",
+];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MaskMode {
+    /// Text is synthesized as is.
+    #[default]
+    Off,
+    /// Masked words are replaced by a substitute word.
+    Substitute,
+    /// Masked words are replaced by a beep tone.
+    Bleep,
+}
+
+/// Profanity masking of tts text. Screenshots still show the original text.
+#[derive(Debug, Default)]
+pub struct Mask {
+    mode: MaskMode,
+    regex: Option<regex::Regex>,
+    replacement: String,
+}
+
+impl Mask {
+    /// Words are matched case insensitively along with their common suffixes (fucking, shitty).
+    /// Built-in list of words is used when `words` is empty.
+    pub fn new(mode: MaskMode, words: &[String], replacement: &str) -> Result<Self> {
+        let mut words = words
+            .iter()
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<_>>();
+
+        if words.is_empty() {
+            words = PROFANITY.to_vec();
+        }
+
+        let regex = match mode {
+            MaskMode::Off => None,
+            _ => Some(regex::Regex::new(&format!(
+                "(?i){}",
+                words
+                    .iter()
+                    .map(|x| whole_word(x, "(?:s|es|ed|er|ers|ing|in|y|ty|ted|ter|ters|ting)?"))
+                    .collect::<Vec<_>>()
+                    .join("|")
+            ))?),
+        };
+
+        Ok(Self {
+            mode,
+            regex,
+            replacement: replacement.to_owned(),
+        })
+    }
+
+    /// Text with masked words replaced by substitute word in substitute mode.
+    pub fn substitute<'a>(&self, text: &'a str) -> std::borrow::Cow<'a, str> {
+        match (&self.mode, &self.regex) {
+            (MaskMode::Substitute, Some(regex)) => {
+                regex.replace_all(text, regex::NoExpand(&self.replacement))
+            }
+            _ => std::borrow::Cow::Borrowed(text),
+        }
+    }
+
+    /// Parts of text around masked words in bleep mode.
+    pub(super) fn bleep<'a>(&self, text: &'a str) -> Option<Vec<&'a str>> {
+        match (&self.mode, &self.regex) {
+            (MaskMode::Bleep, Some(regex)) if regex.is_match(text) => {
+                Some(regex.split(text).collect())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Pattern matching `word` as a whole word, optionally followed by `suffix` pattern.
+/// Word boundaries are used only at alphanumeric ends, so that words like c++ and TL;DR match too.
+pub(crate) fn whole_word(word: &str, suffix: &str) -> String {
+    let boundary = |x: Option<char>| x.is_some_and(|x| x.is_alphanumeric() || x == '_');
+
+    match (boundary(word.chars().next()), boundary(word.chars().last())) {
+        (true, true) => format!(r"\b{}{}\b", regex::escape(word), suffix),
+        (true, false) => format!(r"\b{}", regex::escape(word)),
+        (false, true) => format!(r"{}{}\b", regex::escape(word), suffix),
+        (false, false) => regex::escape(word),
+    }
+}
+
+/// Built-in pronunciation lexicon.
+const LEXICON: &str = include_str!("../../docs/Lexicon.toml");
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct LexiconFile {
+    defaults: bool,
+    words: std::collections::BTreeMap<String, String>,
+    patterns: Vec<LexiconPattern>,
+}
+
+impl Default for LexiconFile {
+    fn default() -> Self {
+        Self {
+            defaults: true,
+            words: std::collections::BTreeMap::new(),
+            patterns: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LexiconPattern {
+    pattern: String,
+    replacement: String,
+}
+
+/// Replacements of slang, acronyms and subreddit names applied to text before synthesis.
+#[derive(Debug, Default)]
+pub struct Lexicon {
+    rules: Vec<(regex::Regex, String)>,
+}
+
+impl Lexicon {
+    /// Built-in lexicon extended by lexicon file at `path`.
+    pub fn load_toml(path: Option<&str>) -> Result<Self> {
+        let mut lexicon = toml::from_str::<LexiconFile>(LEXICON)?;
+
+        if let Some(path) = path {
+            let user = std::fs::read_to_string(path)
+                .with_context(|| format!("Couldn't read lexicon file {}", path))
+                .and_then(|x| {
+                    toml::from_str::<LexiconFile>(&x)
+                        .with_context(|| format!("Couldn't parse lexicon file {}", path))
+                })?;
+
+            if !user.defaults {
+                lexicon = LexiconFile::default();
+            }
+
+            lexicon.words.extend(user.words);
+            lexicon.patterns.extend(user.patterns);
+        }
+
+        let mut rules = vec![];
+
+        // Longer words first so that they aren't shadowed by their parts.
+        let mut words = lexicon.words.into_iter().collect::<Vec<_>>();
+        words.sort_by_key(|(word, _)| std::cmp::Reverse(word.len()));
+
+        for (word, replacement) in words {
+            rules.push((
+                regex::Regex::new(&whole_word(&word, ""))?,
+                replacement.replace('$', "$$"),
+            ));
+        }
+
+        for pattern in lexicon.patterns {
+            rules.push((
+                regex::Regex::new(&pattern.pattern)
+                    .with_context(|| format!("Invalid lexicon pattern {:?}", pattern.pattern))?,
+                pattern.replacement,
+            ));
+        }
+
+        Ok(Self { rules })
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_owned();
+
+        for (regex, replacement) in &self.rules {
+            text = regex.replace_all(&text, replacement.as_str()).into_owned();
+        }
+
+        text
+    }
+}
+
+/// Convert reddit markdown into plain text suitable for narration.
+/// Markup, urls and quote markers are dropped, links are replaced by their text,
+/// spoilers are replaced by the word spoiler and table rows are read as comma separated cells.
+/// Lines (list items, headings, paragraphs) are ended with a full stop so that tts pauses after them.
+pub fn normalize(text: &str) -> String {
+    let markdown = Markdown::get();
+    let text = unescape_html(text);
+    let mut lines = vec![];
+    let mut code_block = false;
+
+    for line in text.lines() {
+        let mut line = line.trim();
+
+        if line.starts_with("```") || line.starts_with("~~~") {
+            code_block = !code_block;
+            continue;
+        }
+
+        if code_block {
+            lines.push(line.to_owned());
+            continue;
+        }
+
+        while line.starts_with('>') && !line.starts_with(">!") {
+            line = line[1..].trim_start();
+        }
+
+        if markdown.rule.is_match(line) || markdown.table_separator.is_match(line) {
+            continue;
+        }
+
+        let line = markdown.heading.replace(line, "");
+        let line = markdown.list.replace(&line, "");
+        let line = if line.starts_with('|') || line.matches('|').count() >= 2 {
+            line.trim_matches('|')
+                .split('|')
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ")
+        } else {
+            line.into_owned()
+        };
+
+        lines.push(normalize_inline(&line));
+    }
+
+    lines
+        .into_iter()
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            if x.ends_with(|x: char| x.is_alphanumeric()) {
+                x + "."
+            } else {
+                x
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Compiled regexes used by [`normalize`], which are built only once.
+struct Markdown {
+    heading: regex::Regex,
+    rule: regex::Regex,
+    table_separator: regex::Regex,
+    list: regex::Regex,
+    entity: regex::Regex,
+    inline: Vec<(regex::Regex, &'static str)>,
+}
+
+impl Markdown {
+    fn get() -> &'static Self {
+        static MARKDOWN: std::sync::OnceLock<Markdown> = std::sync::OnceLock::new();
+
+        MARKDOWN.get_or_init(|| Self {
+            heading: regex::Regex::new(r"^#{1,6}\s+").unwrap(),
+            rule: regex::Regex::new(r"^([-*_]\s*){3,}$").unwrap(),
+            table_separator: regex::Regex::new(r"^\|?(\s*:?-+:?\s*\|)+\s*(:?-+:?\s*)?$").unwrap(),
+            list: regex::Regex::new(r"^([*+-]|\d+[.)])\s+").unwrap(),
+            entity: regex::Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap(),
+            inline: INLINE
+                .iter()
+                .map(|(pattern, replacement)| (regex::Regex::new(pattern).unwrap(), *replacement))
+                .collect(),
+        })
+    }
+}
+
+/// Inline markdown patterns and their replacements, applied in order.
+const INLINE: &[(&str, &str)] = &[
+    // Escaped characters
+    (r"\\([\\`*_{}\[\]()#+\-.!>~|^])", "$1"),
+    // Spoilers
+    (r">!.*?!<", "spoiler"),
+    // Links and images
+    (
+        r#"!?\[([^\]]*)\]\(\s*<?([^()\s]|\([^()\s]*\))*>?(\s+"[^"]*")?\s*\)"#,
+        "$1",
+    ),
+    // Urls
+    (r"<?\b(https?://|www\.)[^\s>]+>?", ""),
+    // Superscripts
+    (r"\^\(([^)]*)\)", "$1"),
+    (r"\^", ""),
+    // Emphasis and strikethrough
+    (r"\*+|~~", ""),
+    (r"(^|\W)_{1,3}([^_]+?)_{1,3}(\W|$)", "$1$2$3"),
+    // Inline code
+    (r"`+", ""),
+    (r"\s+", " "),
+];
+
+fn normalize_inline(text: &str) -> String {
+    let mut text = text.to_owned();
+
+    for (regex, replacement) in &Markdown::get().inline {
+        text = regex.replace_all(&text, *replacement).into_owned();
+    }
+
+    text.trim().to_owned()
+}
+
+/// Reddit escapes &, < and > in markdown and often uses entities like &#x200B; for blank lines.
+fn unescape_html(text: &str) -> String {
+    Markdown::get()
+        .entity
+        .replace_all(text, |captures: &regex::Captures| {
+            let entity = &captures[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "apos" => Some('\''),
+                "gt" => Some('>'),
+                "lt" => Some('<'),
+                "nbsp" => Some(' '),
+                "quot" => Some('"'),
+                _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                    u32::from_str_radix(&entity[2..], 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                }
+                _ if entity.starts_with('#') => {
+                    entity[1..].parse::<u32>().ok().and_then(char::from_u32)
+                }
+                _ => None,
+            };
+
+            match decoded {
+                Some(decoded) => decoded.to_string(),
+                None => captures[0].to_owned(),
+            }
+        })
+        .chars()
+        .filter(|x| !matches!(x, '\u{200B}' | '\u{FEFF}'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize, Lexicon, Mask, MaskMode};
+
+    #[test]
+    fn mask_words() {
+        let mask = Mask::new(MaskMode::Substitute, &[], "beep").unwrap();
+        assert_eq!(
+            mask.substitute("I was pissed, this FUCKING shitty bullshit"),
+            "I was beep, this beep beep beep"
+        );
+        // Only whole words along with their suffixes are masked.
+        assert_eq!(
+            mask.substitute("Dickens visited Scunthorpe and Cockburnspath"),
+            "Dickens visited Scunthorpe and Cockburnspath"
+        );
+        assert_eq!(mask.substitute("dickish dicks"), "dickish beep");
+
+        let mask = Mask::new(
+            MaskMode::Substitute,
+            &[" darn ".to_owned(), "c++".to_owned()],
+            "beep",
+        )
+        .unwrap();
+        assert_eq!(
+            mask.substitute("Darned c++ code, shit"),
+            "beep beep code, shit"
+        );
+    }
+
+    #[test]
+    fn mask_substitute_is_literal() {
+        let mask = Mask::new(MaskMode::Substitute, &[], "$0 [${1}]").unwrap();
+        assert_eq!(mask.substitute("oh shit"), "oh $0 [${1}]");
+    }
+
+    #[test]
+    fn mask_bleep() {
+        let mask = Mask::new(MaskMode::Bleep, &[], "beep").unwrap();
+        assert_eq!(
+            mask.bleep("what the fuck is this shit"),
+            Some(vec!["what the ", " is this ", ""])
+        );
+        assert_eq!(mask.bleep("nothing to hide"), None);
+        // Bleeping doesn't substitute words.
+        assert_eq!(mask.substitute("oh shit"), "oh shit");
+
+        let mask = Mask::new(MaskMode::Off, &[], "beep").unwrap();
+        assert_eq!(mask.bleep("oh shit"), None);
+        assert_eq!(mask.substitute("oh shit"), "oh shit");
+    }
+
+    /// Lexicon extended by lexicon file `name` of `content`.
+    fn user_lexicon(name: &str, content: &str) -> Lexicon {
+        let path =
+            std::env::temp_dir().join(format!("redtake-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        let lexicon = Lexicon::load_toml(path.to_str());
+        std::fs::remove_file(&path).unwrap();
+        lexicon.unwrap()
+    }
+
+    #[test]
+    fn lexicon_words() {
+        let lexicon = Lexicon::load_toml(None).unwrap();
+        assert_eq!(
+            lexicon.apply("AITA? TL;DR: IMO OP is right, tbh."),
+            "am I the asshole? too long, did not read: in my opinion O P is right, to be honest."
+        );
+        // Only whole words are replaced.
+        assert_eq!(lexicon.apply("OPEN the TOP SMHS"), "OPEN the TOP SMHS");
+    }
+
+    #[test]
+    fn lexicon_user_file() {
+        let lexicon = user_lexicon(
+            "extend",
+            "[words]\nOP = 'original poster'\nBF = 'boyfriend'\n",
+        );
+        assert_eq!(
+            lexicon.apply("OP and her BF, AITA"),
+            "original poster and her boyfriend, am I the asshole"
+        );
+
+        let lexicon = user_lexicon("replace", "defaults = false\n[words]\nBF = 'boyfriend'\n");
+        assert_eq!(
+            lexicon.apply("OP and her BF, AITA"),
+            "OP and her boyfriend, AITA"
+        );
+    }
+
+    #[test]
+    fn lexicon_patterns() {
+        let lexicon = Lexicon::load_toml(None).unwrap();
+        assert_eq!(lexicon.apply("great idea /s."), "great idea sarcasm.");
+        assert_eq!(lexicon.apply("/s"), "sarcasm");
+        assert_eq!(
+            lexicon.apply("ask r/AskReddit or u/some-user, w/o help"),
+            "ask r slash AskReddit or u slash some-user, without help"
+        );
+        assert_eq!(lexicon.apply("coffee w/ milk"), "coffee with milk");
+    }
+
+    #[test]
+    fn lexicon_after_normalize() {
+        let lexicon = Lexicon::load_toml(None).unwrap();
+        assert_eq!(
+            lexicon.apply(&normalize(
+                "[my post](https://www.reddit.com/r/AITA/comments/abc/x/) AITA?"
+            )),
+            "my post am I the asshole?"
+        );
+        assert_eq!(
+            lexicon.apply(&normalize("IMO see https://example.com/IMO/page")),
+            "in my opinion see."
+        );
+    }
+
+    #[test]
+    fn entities() {
+        assert_eq!(normalize("Tom &amp; Jerry &lt;3"), "Tom & Jerry <3.");
+        assert_eq!(normalize("it&#39;s &#x27;fine&#x27;"), "it's 'fine'");
+        assert_eq!(normalize("first\n\n&#x200B;\n\nsecond"), "first. second.");
+        assert_eq!(normalize("&unknown; entity"), "&unknown; entity.");
+    }
+
+    #[test]
+    fn links_and_images() {
+        assert_eq!(
+            normalize("read [the rules](https://reddit.com/r/x/wiki) first"),
+            "read the rules first."
+        );
+        assert_eq!(
+            normalize("see [wiki](https://en.wikipedia.org/wiki/Rust_(language)) now"),
+            "see wiki now."
+        );
+        assert_eq!(
+            normalize("![cat](https://i.imgur.com/cat.png \"title\") here"),
+            "cat here."
+        );
+    }
+
+    #[test]
+    fn urls() {
+        assert_eq!(
+            normalize("go to https://example.com/a?b=c or www.example.org now"),
+            "go to or now."
+        );
+        assert_eq!(normalize("<https://example.com> link"), "link.");
+    }
+
+    #[test]
+    fn quotes() {
+        assert_eq!(normalize("> quoted text\n\nreply"), "quoted text. reply.");
+        assert_eq!(normalize(">> nested quote"), "nested quote.");
+        assert_eq!(normalize(">!hidden!< text"), "spoiler text.");
+    }
+
+    #[test]
+    fn lists_and_headings() {
+        assert_eq!(
+            normalize("# Title\n\n* one\n- two\n+ three\n1. four\n2) five"),
+            "Title. one. two. three. four. five."
+        );
+        assert_eq!(normalize("---\n***\ntext"), "text.");
+    }
+
+    #[test]
+    fn tables() {
+        assert_eq!(
+            normalize("| Name | Age |\n|:---|---:|\n| Bob | 42 |"),
+            "Name, Age. Bob, 42."
+        );
+    }
+
+    #[test]
+    fn spoilers() {
+        assert_eq!(
+            normalize("the ending >!he dies!< was sad"),
+            "the ending spoiler was sad."
+        );
+    }
+
+    #[test]
+    fn superscripts() {
+        assert_eq!(normalize("so ^(tiny text) and x^2"), "so tiny text and x2.");
+    }
+
+    #[test]
+    fn emphasis_and_escapes() {
+        assert_eq!(
+            normalize("**bold** *italic* ~~gone~~ _under_ `code` \\*star\\*"),
+            "bold italic gone under code star."
+        );
+        assert_eq!(normalize("snake_case_name"), "snake_case_name.");
+    }
+
+    #[test]
+    fn code_blocks() {
+        assert_eq!(
+            normalize("run this:\n```\nlet *x* = 1;\n```\ndone"),
+            "run this: let *x* = 1; done."
+        );
+    }
+}
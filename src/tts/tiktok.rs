@@ -0,0 +1,94 @@
+use anyhow::{bail, Result};
+
+use super::{TtsProvider, Voice};
+use crate::client::Client;
+
+/// Voice name, speaker id and language of voices.
+const VOICES: &[(&str, &str, &str)] = &[
+    ("de-de-tiktok-female", "de_001", "de-DE"),
+    ("de-de-tiktok-male", "de_002", "de-DE"),
+    ("en-au-tiktok-female", "en_au_001", "en-AU"),
+    ("en-au-tiktok-male", "en_au_002", "en-AU"),
+    ("en-uk-tiktok-male1", "en_uk_001", "en-GB"),
+    ("en-uk-tiktok-male2", "en_uk_003", "en-GB"),
+    ("en-us-tiktok-c3po", "en_us_c3po", "en-US"),
+    ("en-us-tiktok-chewbacca", "en_us_chewbacca", "en-US"),
+    ("en-us-tiktok-female1", "en_us_001", "en-US"),
+    ("en-us-tiktok-female2", "en_us_002", "en-US"),
+    ("en-us-tiktok-ghostface", "en_us_ghostface", "en-US"),
+    ("en-us-tiktok-male1", "en_us_006", "en-US"),
+    ("en-us-tiktok-male2", "en_us_007", "en-US"),
+    ("en-us-tiktok-male3", "en_us_009", "en-US"),
+    ("en-us-tiktok-rocket", "en_us_rocket", "en-US"),
+    ("en-us-tiktok-stitch", "en_us_stitch", "en-US"),
+    ("en-us-tiktok-stormtrooper", "en_us_stormtrooper", "en-US"),
+    ("en-us-tiktok-unknown", "en_us_010", "en-US"),
+    ("es-es-tiktok-male", "es_002", "es-ES"),
+    ("es-mx-tiktok-male", "es_mx_002", "es-MX"),
+    ("fr-fr-tiktok-male1", "fr_001", "fr-FR"),
+    ("fr-fr-tiktok-male2", "fr_002", "fr-FR"),
+    ("id-id-tiktok-female", "id_001", "id-ID"),
+    ("jp-jp-tiktok-female1", "jp_001", "ja-JP"),
+    ("jp-jp-tiktok-female2", "jp_003", "ja-JP"),
+    ("jp-jp-tiktok-female3", "jp_005", "ja-JP"),
+    ("jp-jp-tiktok-male", "jp_006", "ja-JP"),
+    ("kr-kr-tiktok-female", "kr_003", "ko-KR"),
+    ("kr-kr-tiktok-male1", "kr_002", "ko-KR"),
+    ("kr-kr-tiktok-male2", "kr_004", "ko-KR"),
+    ("pt-br-tiktok-female1", "br_001", "pt-BR"),
+    ("pt-br-tiktok-female2", "br_003", "pt-BR"),
+    ("pt-br-tiktok-female3", "br_004", "pt-BR"),
+    ("pt-br-tiktok-male", "br_005", "pt-BR"),
+];
+
+/// TikTok text to speech api.
+pub struct TikTok {
+    proxy: bool,
+}
+
+impl TikTok {
+    pub fn new(proxy: bool) -> Self {
+        Self { proxy }
+    }
+}
+
+impl TtsProvider for TikTok {
+    fn voices(&self) -> Vec<Voice> {
+        VOICES
+            .iter()
+            .map(|(name, id, language)| Voice::new(name, id, language))
+            .collect()
+    }
+
+    fn max_chunk_length(&self) -> usize {
+        299
+    }
+
+    fn synthesize(&self, client: &Client, voice: &Voice, text: &str) -> Result<Vec<u8>> {
+        let url = if self.proxy {
+            "https://warp-co.rs/https://api16-normal-useast5.us.tiktokv.com/media/api/text/speech/invoke/"
+        } else {
+            "https://api16-normal-useast5.us.tiktokv.com/media/api/text/speech/invoke/"
+        };
+
+        let mut res = client.post(url).query(&[
+            ("text_speaker", voice.id.as_str()),
+            ("req_text", text),
+            ("speaker_map_type", "0"),
+        ]);
+
+        if self.proxy {
+            res = res.header("Origin", "https://api16-normal-useast5.us.tiktokv.com")
+        }
+
+        let res = client.json::<serde_json::Value>(res)?;
+
+        match res["data"]["v_str"].as_str() {
+            Some(data) if !data.is_empty() => Ok(base64::decode(data)?),
+            _ => bail!(res["message"]
+                .as_str()
+                .unwrap_or("Couldn't perform tts.")
+                .to_owned()),
+        }
+    }
+}
@@ -40,6 +40,8 @@ struct OverlayMeta {
     image: String,
     audio: String,
     duration: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    media: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -98,9 +100,17 @@ impl Project {
             image: image.to_owned(),
             audio: tts.to_owned(),
             duration: duration,
+            media: vec![],
         });
     }
 
+    /// Show media images in turns below last added overlay while its audio plays.
+    pub fn add_media(&mut self, media: Vec<String>) {
+        if let Some(overlay) = self.overlay.overlays.last_mut() {
+            overlay.media = media;
+        }
+    }
+
     pub fn len(&self) -> f32 {
         self.overlay
             .overlays
@@ -179,8 +189,20 @@ impl Project {
         for overlay in &self.overlay.overlays {
             args.push("-i");
             args.push(&overlay.image);
+
+            for media in &overlay.media {
+                args.push("-i");
+                args.push(media);
+            }
         }
 
+        let images = self
+            .overlay
+            .overlays
+            .iter()
+            .map(|x| 1 + x.media.len())
+            .sum::<usize>();
+
         // TTS
         args.push("-i");
         args.push("audio/tts.mp3");
@@ -223,9 +245,9 @@ impl Project {
         // Comments Overlay
         let mut previous_duration = 0.0;
 
-        for (i, overlay) in self.overlay.overlays.iter().enumerate() {
-            let index = if has_background { i + 1 } else { i };
+        let mut index = if has_background { 1 } else { 0 };
 
+        for overlay in &self.overlay.overlays {
             // Scale
             filter_complex += &format!(
                 "[{}:v]scale={}-{}:-1[overlay{}];",
@@ -241,7 +263,41 @@ impl Project {
             }
 
             // Duration
-            filter_complex += &format!("[main][overlay{}]overlay=(main_w-overlay_w)/2:(main_h-overlay_h)/2:enable='between(t,{},{})'[main];", index, previous_duration, previous_duration + overlay.duration);
+            if overlay.media.is_empty() {
+                filter_complex += &format!("[main][overlay{}]overlay=(main_w-overlay_w)/2:(main_h-overlay_h)/2:enable='between(t,{},{})'[main];", index, previous_duration, previous_duration + overlay.duration);
+            } else {
+                // Move to top leaving lower half for media.
+                filter_complex += &format!("[main][overlay{}]overlay=(main_w-overlay_w)/2:{}:enable='between(t,{},{})'[main];", index, self.overlay.padding / 2, previous_duration, previous_duration + overlay.duration);
+            }
+
+            index += 1;
+
+            // Media
+            let slice = overlay.duration / overlay.media.len().max(1) as f32;
+
+            for i in 0..overlay.media.len() {
+                // Fit inside lower half
+                filter_complex += &format!(
+                    "[{}:v]scale={}-{}:{}/2-{}:force_original_aspect_ratio=decrease[overlay{}];",
+                    index,
+                    self.video.width,
+                    self.overlay.padding,
+                    self.video.height,
+                    self.overlay.padding,
+                    index
+                );
+
+                if self.overlay.opacity < 0.99 {
+                    filter_complex += &format!(
+                        "[overlay{}]format=argb,colorchannelmixer=aa={}[overlay{}];",
+                        index, self.overlay.opacity, index
+                    );
+                }
+
+                filter_complex += &format!("[main][overlay{}]overlay=(main_w-overlay_w)/2:main_h*3/4-overlay_h/2:enable='between(t,{},{})'[main];", index, previous_duration + slice * i as f32, previous_duration + slice * (i + 1) as f32);
+                index += 1;
+            }
+
             previous_duration += overlay.duration;
         }
 
@@ -270,16 +326,12 @@ impl Project {
         let audio_filter_complex = format!(
             "[{}:a]volume={}[bgm];[{}:a][bgm]amerge=inputs=2[bgm]",
             if has_background {
-                self.overlay.overlays.len() + 2
+                images + 2
             } else {
-                self.overlay.overlays.len() + 1
+                images + 1
             },
             self.music.volume,
-            if has_background {
-                self.overlay.overlays.len() + 1
-            } else {
-                self.overlay.overlays.len()
-            }
+            if has_background { images + 1 } else { images }
         );
 
         // Add Filters
@@ -296,9 +348,9 @@ impl Project {
 
         // Map Audio
         let audio = if has_background {
-            format!("{}:a", self.overlay.overlays.len() + 1)
+            format!("{}:a", images + 1)
         } else {
-            format!("{}:a", self.overlay.overlays.len())
+            format!("{}:a", images)
         };
 
         if has_bgm {